@@ -1,74 +1,300 @@
 use anyhow::Result;
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
 use cpal::{Device, Sample, SizedSample};
-use std::sync::mpsc;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
 use std::thread;
-use std::time::{Duration, Instant};
-use log::{error, info};
+use log::{error, info, warn};
 
 pub const WHISPER_SAMPLE_RATE: u32 = 16000;
 const SILENCE_THRESHOLD: f32 = 0.01; 
-const SILENCE_DURATION_MS: u128 = 2000; 
+// Seuil de maintien : une fois la parole détectée, on tolère un signal plus faible
+const SILENCE_RELEASE_THRESHOLD: f32 = SILENCE_THRESHOLD * 0.5;
+const SILENCE_DURATION_MS: u32 = 2000; 
+// Crête en dessous de laquelle un enregistrement est considéré muet (micro coupé),
+// bien plus bas que SILENCE_THRESHOLD pour ne pas écarter une voix faible
+const SILENT_FLOOR: f32 = 0.001;
+// Au-delà, un échantillon est compté comme saturé
+const CLIPPING_LEVEL: f32 = 0.99;
+const PRE_EMPHASIS_COEFF: f32 = 0.97;
+// Taille des trames pour l'analyse du silence hors temps réel
+const VAD_FRAME_MS: u32 = 30;
+// Recherche de l'attaque avant le premier échantillon au-dessus du seuil :
+// fenêtres de 10ms, au plus 200ms en arrière (à 16kHz)
+const ATTACK_WINDOW: usize = 160;
+const MAX_ATTACK_LOOKBACK: usize = 3200;
+// En dessous de SILENCE_THRESHOLD * ce ratio, c'est du bruit de fond
+const ATTACK_FLOOR_RATIO: f32 = 0.1;
+// Latence max du retour micro : au-delà, les plus vieux échantillons sont jetés
+const MONITOR_MAX_LATENCY_MS: u32 = 50;
+
+// Identifiant des enregistrements, unique pour tout le processus (même après
+// réouverture du périphérique), pour relier capture et transcription dans les logs
+static NEXT_RECORDING_ID: AtomicU64 = AtomicU64::new(1);
+
+pub const MIN_GAIN: f32 = 0.5;
+pub const MAX_GAIN: f32 = 4.0;
+
+// Réglages du recorder, modifiables pendant l'exécution
+#[derive(Debug, Clone, Copy)]
+pub struct RecorderConfig {
+    // Filtre de pré-accentuation sur le buffer final (désactivé par défaut)
+    pub pre_emphasis: bool,
+    // Gain d'entrée appliqué à chaque échantillon (MIN_GAIN..=MAX_GAIN)
+    pub gain: f32,
+    // Retour du micro sur la sortie par défaut pendant l'enregistrement
+    // (désactivé par défaut pour éviter l'effet Larsen)
+    pub monitor: bool,
+    // Arrêt automatique après SILENCE_DURATION_MS de silence (activé par défaut)
+    pub auto_stop: bool,
+    // Durée minimale d'un enregistrement, en dessous il n'est pas transcrit
+    pub min_duration_ms: u32,
+    // Garde une copie du buffer brut (avant resample/trim), désactivé par défaut
+    pub keep_raw: bool,
+    // Fréquence de capture forcée (ex. 48000), `None` = 16kHz si supporté.
+    // Lue à l'ouverture du flux : un changement nécessite de recréer le recorder.
+    pub capture_rate: Option<u32>,
+}
+
+impl Default for RecorderConfig {
+    fn default() -> Self {
+        Self {
+            pre_emphasis: false,
+            gain: 1.0,
+            monitor: false,
+            auto_stop: true,
+            min_duration_ms: 500,
+            keep_raw: false,
+            capture_rate: None,
+        }
+    }
+}
 
 // Nouvel enum pour les événements sortants
 pub enum AudioEvent {
-    AutoStopped(Vec<f32>),
+    AutoStopped(Recording),
 }
 
+// Décision du VAD pour un bloc d'échantillons
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VadDecision {
+    Speech,
+    Silence,
+    // Silence continu plus long que la durée configurée
+    AutoStop,
+}
+
+// Détection parole/silence sur l'amplitude crête, avec hystérésis.
+// Le temps est compté en échantillons (et non en temps réel) pour rester
+// déterministe quel que soit le découpage des blocs. L'arrêt automatique
+// n'est proposé qu'après une première parole : un silence initial (le temps
+// de commencer à parler, en dictée continue notamment) ne coupe rien.
+#[derive(Debug, Clone)]
+pub struct Vad {
+    onset_threshold: f32,
+    release_threshold: f32,
+    stop_after_samples: usize,
+    speaking: bool,
+    // Parole détectée depuis le dernier reset
+    heard_speech: bool,
+    silent_samples: usize,
+}
+
+impl Vad {
+    pub fn new(sample_rate: u32, silence_ms: u32) -> Self {
+        Self {
+            onset_threshold: SILENCE_THRESHOLD,
+            release_threshold: SILENCE_RELEASE_THRESHOLD,
+            stop_after_samples: sample_rate as usize * silence_ms as usize / 1000,
+            speaking: false,
+            heard_speech: false,
+            silent_samples: 0,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.speaking = false;
+        self.heard_speech = false;
+        self.silent_samples = 0;
+    }
+
+    pub fn process(&mut self, chunk: &[f32]) -> VadDecision {
+        let max_amplitude = chunk.iter().fold(0.0f32, |max, &x| max.max(x.abs()));
+        let threshold = if self.speaking { self.release_threshold } else { self.onset_threshold };
+        self.speaking = max_amplitude > threshold;
+
+        if self.speaking {
+            self.heard_speech = true;
+            self.silent_samples = 0;
+            return VadDecision::Speech;
+        }
+
+        self.silent_samples += chunk.len();
+        if self.heard_speech && self.silent_samples > self.stop_after_samples {
+            VadDecision::AutoStop
+        } else {
+            VadDecision::Silence
+        }
+    }
+}
+
+// Résultat d'un arrêt (manuel ou automatique)
+pub enum Recording {
+    // Buffer final, prêt pour Whisper
+    Samples { id: u64, samples: Vec<f32>, stats: RecordingStats },
+    // Plus court que `min_duration_ms` (clic accidentel) : jeté
+    TooShort,
+    // Aucun signal exploitable (micro coupé ?) : inutile de lancer Whisper
+    Silent,
+    // Du signal, mais rien au-dessus du seuil de parole (bruit de fond) : jeté
+    NoSpeech,
+}
+
+// Périphérique d'entrée proposé dans le sélecteur
+#[derive(Debug, Clone)]
+pub struct InputDevice {
+    pub name: String,
+    // Source "monitor" (son du système) exposée par PulseAudio/PipeWire
+    pub is_monitor: bool,
+}
+
+// File d'échantillons partagée entre la capture et la sortie de monitoring
+type MonitorQueue = Arc<Mutex<VecDeque<f32>>>;
+// Dernier buffer brut (fréquence du périphérique) et sa fréquence
+type RawRecording = Arc<Mutex<Option<(Vec<f32>, u32)>>>;
+
 enum Cmd {
     Start,
-    Stop(mpsc::Sender<Vec<f32>>),
+    Stop(mpsc::Sender<Recording>),
+    Cancel,
+    SetConfig(RecorderConfig),
     Shutdown,
 }
 
+// Commandes et échantillons passent par le même canal : le thread audio
+// bloque sur `recv` et traite une commande dès son arrivée, sans polling.
+enum WorkerMsg {
+    Cmd(Cmd),
+    Samples(Vec<f32>),
+}
+
 pub struct AudioRecorder {
-    cmd_tx: Option<mpsc::Sender<Cmd>>,
+    cmd_tx: Option<mpsc::Sender<WorkerMsg>>,
     worker_handle: Option<thread::JoinHandle<()>>,
+    config: RecorderConfig,
+    last_raw: RawRecording,
+    // Nom du périphérique réellement ouvert (après repli éventuel)
+    device_name: String,
+    // Niveau crête du dernier bloc enregistré (f32 stocké sous forme de bits)
+    level: Arc<AtomicU32>,
 }
 
 unsafe impl Send for AudioRecorder {}
 
 impl AudioRecorder {
-    // On passe un Sender pour recevoir les événements automatiques
-    pub fn new(event_tx: mpsc::Sender<AudioEvent>) -> Result<Self> {
+    // On passe un Sender pour recevoir les événements automatiques.
+    // `device_name` : périphérique choisi par l'utilisateur, `None` = défaut.
+    pub fn new(event_tx: mpsc::Sender<AudioEvent>, device_name: Option<&str>, config: RecorderConfig) -> Result<Self> {
         let mut recorder = Self {
             cmd_tx: None,
             worker_handle: None,
+            config,
+            last_raw: Arc::new(Mutex::new(None)),
+            device_name: String::new(),
+            level: Arc::new(AtomicU32::new(0)),
         };
-        recorder.init_stream(event_tx)?;
+        recorder.init_stream(event_tx, device_name)?;
+        recorder.set_config(config)?;
         Ok(recorder)
     }
 
     pub fn start_recording(&mut self) -> Result<()> {
         if let Some(tx) = &self.cmd_tx {
-            tx.send(Cmd::Start).map_err(|e| anyhow::anyhow!("Failed to send Start: {}", e))?;
+            tx.send(WorkerMsg::Cmd(Cmd::Start)).map_err(|e| anyhow::anyhow!("Failed to send Start: {}", e))?;
         }
         Ok(())
     }
 
-    pub fn stop_recording(&mut self) -> Result<Vec<f32>> {
+    pub fn stop_recording(&mut self) -> Result<Recording> {
         let (resp_tx, resp_rx) = mpsc::channel();
         if let Some(tx) = &self.cmd_tx {
-            tx.send(Cmd::Stop(resp_tx)).map_err(|e| anyhow::anyhow!("Failed to send Stop: {}", e))?;
-            let samples = resp_rx.recv().map_err(|e| anyhow::anyhow!("Failed to receive samples: {}", e))?;
-            return Ok(samples);
+            tx.send(WorkerMsg::Cmd(Cmd::Stop(resp_tx))).map_err(|e| anyhow::anyhow!("Failed to send Stop: {}", e))?;
+            let recording = resp_rx.recv().map_err(|e| anyhow::anyhow!("Failed to receive samples: {}", e))?;
+            return Ok(recording);
         }
-        Ok(Vec::new())
+        Ok(Recording::Samples { id: 0, samples: Vec::new(), stats: RecordingStats::default() })
     }
 
-    fn init_stream(&mut self, event_tx: mpsc::Sender<AudioEvent>) -> Result<()> {
-        let host = cpal::default_host();
-        let device = host.default_input_device().ok_or(anyhow::anyhow!("No input device found"))?;
+    // Abandonne l'enregistrement en cours sans renvoyer d'échantillons
+    pub fn cancel_recording(&mut self) -> Result<()> {
+        if let Some(tx) = &self.cmd_tx {
+            tx.send(WorkerMsg::Cmd(Cmd::Cancel)).map_err(|e| anyhow::anyhow!("Failed to send Cancel: {}", e))?;
+        }
+        Ok(())
+    }
+
+    // Buffer brut du dernier arrêt, si `keep_raw` était actif
+    pub fn last_raw_recording(&self) -> Option<(Vec<f32>, u32)> {
+        self.last_raw.lock().unwrap().clone()
+    }
+
+    // Niveau crête 0.0..=1.0 du dernier bloc, pour un vumètre (0 hors enregistrement)
+    pub fn level(&self) -> f32 {
+        f32::from_bits(self.level.load(Ordering::Relaxed))
+    }
+
+    pub fn device_name(&self) -> &str {
+        &self.device_name
+    }
+
+    pub fn config(&self) -> RecorderConfig {
+        self.config
+    }
+
+    pub fn set_config(&mut self, config: RecorderConfig) -> Result<()> {
+        self.config = config;
+        if let Some(tx) = &self.cmd_tx {
+            tx.send(WorkerMsg::Cmd(Cmd::SetConfig(config))).map_err(|e| anyhow::anyhow!("Failed to send SetConfig: {}", e))?;
+        }
+        Ok(())
+    }
 
-        let (sample_tx, sample_rx) = mpsc::channel::<Vec<f32>>();
-        let (cmd_tx, cmd_rx) = mpsc::channel::<Cmd>();
+    fn init_stream(&mut self, event_tx: mpsc::Sender<AudioEvent>, device_name: Option<&str>) -> Result<()> {
+        let device = select_input_device(&cpal::default_host(), device_name)?;
+        self.device_name = device.name().unwrap_or_else(|_| "unknown device".to_string());
+
+        let (cmd_tx, cmd_rx) = mpsc::channel::<WorkerMsg>();
+        let sample_tx = cmd_tx.clone();
+        let capture_rate = self.config.capture_rate;
+        let last_raw = self.last_raw.clone();
+        let level = self.level.clone();
+        // Le flux est ouvert dans le thread (cpal::Stream n'est pas Send) :
+        // un échec remonte à l'appelant au lieu de laisser un recorder muet
+        let (ready_tx, ready_rx) = mpsc::channel::<Result<()>>();
 
         let worker = thread::spawn(move || {
-            if let Err(e) = run_audio_thread(device, sample_tx, sample_rx, cmd_rx, event_tx) {
-                error!("Audio thread error: {}", e);
-            }
+            let (stream, sample_rate, gain) = match open_capture(&device, capture_rate, sample_tx) {
+                Ok(capture) => {
+                    let _ = ready_tx.send(Ok(()));
+                    capture
+                }
+                Err(e) => {
+                    let _ = ready_tx.send(Err(e));
+                    return;
+                }
+            };
+            run_audio_thread(stream, sample_rate, gain, cmd_rx, event_tx, last_raw, level);
         });
 
+        let ready = ready_rx
+            .recv()
+            .unwrap_or_else(|_| Err(anyhow::anyhow!("Audio thread exited before opening the stream")));
+        if let Err(e) = ready {
+            let _ = worker.join();
+            return Err(e);
+        }
+
         self.cmd_tx = Some(cmd_tx);
         self.worker_handle = Some(worker);
 
@@ -79,7 +305,7 @@ impl AudioRecorder {
 impl Drop for AudioRecorder {
     fn drop(&mut self) {
         if let Some(tx) = self.cmd_tx.take() {
-            let _ = tx.send(Cmd::Shutdown);
+            let _ = tx.send(WorkerMsg::Cmd(Cmd::Shutdown));
         }
         if let Some(h) = self.worker_handle.take() {
             let _ = h.join();
@@ -87,94 +313,233 @@ impl Drop for AudioRecorder {
     }
 }
 
-fn run_audio_thread(
-    device: Device,
-    sample_tx: mpsc::Sender<Vec<f32>>,
-    sample_rx: mpsc::Receiver<Vec<f32>>,
-    cmd_rx: mpsc::Receiver<Cmd>,
-    event_tx: mpsc::Sender<AudioEvent>, // Nouveau canal
-) -> Result<()> {
-    let config = get_preferred_config(&device)?;
+// Ordre de repli : périphérique choisi, puis défaut du système, puis premier
+// périphérique d'entrée énuméré (certaines configs Linux n'ont pas de défaut).
+fn select_input_device(host: &cpal::Host, device_name: Option<&str>) -> Result<Device> {
+    if let Some(name) = device_name {
+        let found = host
+            .input_devices()
+            .ok()
+            .and_then(|mut devices| devices.find(|d| d.name().map(|n| n == name).unwrap_or(false)));
+        match found {
+            Some(device) => return Ok(device),
+            None => warn!("Input device {:?} not found, falling back to default", name),
+        }
+    }
+
+    if let Some(device) = host.default_input_device() {
+        return Ok(device);
+    }
+    warn!("No default input device, falling back to the first available input");
+
+    let device = host
+        .input_devices()
+        .ok()
+        .and_then(|mut devices| devices.next())
+        .ok_or(anyhow::anyhow!("No input device found (no default and none enumerated)"))?;
+    info!("Using first available input device: {:?}", device.name().unwrap_or_default());
+    Ok(device)
+}
+
+// Ouvre et démarre le flux de capture. Renvoie le flux (à garder vivant),
+// la fréquence négociée et le gain partagé avec le callback.
+fn open_capture(
+    device: &Device,
+    capture_rate: Option<u32>,
+    sample_tx: mpsc::Sender<WorkerMsg>,
+) -> Result<(cpal::Stream, u32, Arc<AtomicU32>)> {
+    let config = get_preferred_config(device, capture_rate)?;
     let sample_rate = config.sample_rate().0;
     let channels = config.channels() as usize;
+    if channels == 0 {
+        return Err(anyhow::anyhow!("Input device {:?} reports 0 channels", device.name().unwrap_or_default()));
+    }
+
+    info!("Audio device: {:?}, Negotiated rate: {} Hz, Channels: {}", device.name().unwrap_or_default(), sample_rate, channels);
 
-    info!("Audio device: {:?}, Rate: {}, Channels: {}", device.name().unwrap_or_default(), sample_rate, channels);
+    // Gain partagé avec le callback audio (f32 stocké sous forme de bits)
+    let gain = Arc::new(AtomicU32::new(RecorderConfig::default().gain.to_bits()));
 
     let stream = match config.sample_format() {
-        cpal::SampleFormat::F32 => build_stream::<f32>(&device, &config.into(), sample_tx, channels),
-        cpal::SampleFormat::I16 => build_stream::<i16>(&device, &config.into(), sample_tx, channels),
-        cpal::SampleFormat::U16 => build_stream::<u16>(&device, &config.into(), sample_tx, channels),
-        cpal::SampleFormat::I8 => build_stream::<i8>(&device, &config.into(), sample_tx, channels),
-        cpal::SampleFormat::U8 => build_stream::<u8>(&device, &config.into(), sample_tx, channels),
-        cpal::SampleFormat::I32 => build_stream::<i32>(&device, &config.into(), sample_tx, channels),
-        cpal::SampleFormat::U32 => build_stream::<u32>(&device, &config.into(), sample_tx, channels),
-        cpal::SampleFormat::F64 => build_stream::<f64>(&device, &config.into(), sample_tx, channels),
-        cpal::SampleFormat::I64 => build_stream::<i64>(&device, &config.into(), sample_tx, channels),
-        cpal::SampleFormat::U64 => build_stream::<u64>(&device, &config.into(), sample_tx, channels),
+        cpal::SampleFormat::F32 => build_stream::<f32>(device, &config.into(), sample_tx, channels, gain.clone()),
+        cpal::SampleFormat::I16 => build_stream::<i16>(device, &config.into(), sample_tx, channels, gain.clone()),
+        cpal::SampleFormat::U16 => build_stream::<u16>(device, &config.into(), sample_tx, channels, gain.clone()),
+        cpal::SampleFormat::I8 => build_stream::<i8>(device, &config.into(), sample_tx, channels, gain.clone()),
+        cpal::SampleFormat::U8 => build_stream::<u8>(device, &config.into(), sample_tx, channels, gain.clone()),
+        cpal::SampleFormat::I32 => build_stream::<i32>(device, &config.into(), sample_tx, channels, gain.clone()),
+        cpal::SampleFormat::U32 => build_stream::<u32>(device, &config.into(), sample_tx, channels, gain.clone()),
+        cpal::SampleFormat::F64 => build_stream::<f64>(device, &config.into(), sample_tx, channels, gain.clone()),
+        cpal::SampleFormat::I64 => build_stream::<i64>(device, &config.into(), sample_tx, channels, gain.clone()),
+        cpal::SampleFormat::U64 => build_stream::<u64>(device, &config.into(), sample_tx, channels, gain.clone()),
         _ => return Err(anyhow::anyhow!("Unsupported sample format: {:?}", config.sample_format())),
     }?;
 
     stream.play()?;
+    Ok((stream, sample_rate, gain))
+}
 
+// `_stream` est gardé jusqu'à la fin de la boucle : son drop arrête la capture
+fn run_audio_thread(
+    _stream: cpal::Stream,
+    sample_rate: u32,
+    gain: Arc<AtomicU32>,
+    rx: mpsc::Receiver<WorkerMsg>,
+    event_tx: mpsc::Sender<AudioEvent>,
+    last_raw: RawRecording,
+    level: Arc<AtomicU32>,
+) {
     let mut buffer = Vec::with_capacity(16000 * 600);
+    let mut config = RecorderConfig::default();
+    let monitor_queue: MonitorQueue = Arc::new(Mutex::new(VecDeque::new()));
+    let monitor_max_len = (sample_rate / 1000 * MONITOR_MAX_LATENCY_MS) as usize;
+    let mut monitor_stream: Option<cpal::Stream> = None;
     let mut recording = false;
-    let mut last_speech_time = Instant::now();
-
-    loop {
-        // 1. Commandes
-        if let Ok(cmd) = cmd_rx.try_recv() {
-            match cmd {
-                Cmd::Start => {
-                    buffer.clear();
-                    recording = true;
-                    last_speech_time = Instant::now();
-                    info!("Recording started");
+    let mut recording_id = 0;
+    let mut vad = Vad::new(sample_rate, SILENCE_DURATION_MS);
+
+    // Se termine sur Shutdown, ou si tous les émetteurs ont disparu
+    while let Ok(msg) = rx.recv() {
+        match msg {
+            WorkerMsg::Cmd(Cmd::Start) => {
+                buffer.clear();
+                recording = true;
+                vad.reset();
+                recording_id = NEXT_RECORDING_ID.fetch_add(1, Ordering::Relaxed);
+                info!("Recording #{} started", recording_id);
+            }
+            WorkerMsg::Cmd(Cmd::Stop(reply_tx)) => {
+                recording = false;
+                info!("Recording #{} stopped ({} samples)", recording_id, buffer.len());
+                let _ = reply_tx.send(finish_recording(recording_id, &buffer, sample_rate, &config, &last_raw));
+            }
+            WorkerMsg::Cmd(Cmd::Cancel) => {
+                recording = false;
+                buffer.clear();
+                info!("Recording #{} cancelled", recording_id);
+            }
+            WorkerMsg::Cmd(Cmd::SetConfig(new_config)) => {
+                config = new_config;
+                if !config.keep_raw {
+                    *last_raw.lock().unwrap() = None;
                 }
-                Cmd::Stop(reply_tx) => {
-                    recording = false;
-                    let mut final_samples = process_buffer(&buffer, sample_rate);
-                    trim_silence(&mut final_samples, SILENCE_THRESHOLD);
-                    let _ = reply_tx.send(final_samples);
+                gain.store(config.gain.clamp(MIN_GAIN, MAX_GAIN).to_bits(), Ordering::Relaxed);
+
+                if config.monitor && monitor_stream.is_none() {
+                    match build_monitor_stream(sample_rate, monitor_queue.clone()) {
+                        Ok(stream) => {
+                            info!("Monitoring enabled");
+                            monitor_stream = Some(stream);
+                        }
+                        Err(e) => error!("Monitoring unavailable: {}", e),
+                    }
+                } else if !config.monitor && monitor_stream.take().is_some() {
+                    // Le drop du stream arrête la sortie, la capture continue
+                    monitor_queue.lock().unwrap().clear();
+                    info!("Monitoring disabled");
                 }
-                Cmd::Shutdown => break,
             }
-        }
+            WorkerMsg::Cmd(Cmd::Shutdown) => {
+                // Fermeture pendant un enregistrement : la fin n'est pas perdue,
+                // elle part comme un arrêt automatique (pas de canal de réponse
+                // à attendre, le join du Drop ne peut pas bloquer).
+                if recording && !buffer.is_empty() {
+                    info!("Recording #{}: shutdown while recording, finalizing {} samples", recording_id, buffer.len());
+                    let _ = event_tx.send(AudioEvent::AutoStopped(finish_recording(recording_id, &buffer, sample_rate, &config, &last_raw)));
+                }
+                break;
+            }
+            // Le VAD compte le silence en échantillons : l'auto-stop avance
+            // au rythme des blocs reçus, sans minuterie.
+            WorkerMsg::Samples(chunk) => {
+                if !recording {
+                    level.store(0, Ordering::Relaxed);
+                    continue;
+                }
 
-        // 2. Audio
-        match sample_rx.recv_timeout(Duration::from_millis(50)) {
-            Ok(chunk) => {
-                if recording {
-                    let max_amplitude = chunk.iter().fold(0.0f32, |max, &x| max.max(x.abs()));
+                let peak = chunk.iter().fold(0.0f32, |max, &x| max.max(x.abs()));
+                level.store(peak.to_bits(), Ordering::Relaxed);
+
+                if monitor_stream.is_some() {
+                    let mut queue = monitor_queue.lock().unwrap();
+                    queue.extend(chunk.iter().copied());
+                    let excess = queue.len().saturating_sub(monitor_max_len);
+                    queue.drain(..excess);
+                }
+
+                // Le VAD tourne toujours, seule la finalisation dépend de `auto_stop`
+                let decision = vad.process(&chunk);
+                if config.auto_stop && decision == VadDecision::AutoStop && !buffer.is_empty() {
+                    info!("Recording #{}: silence auto-stop triggered ({} samples)", recording_id, buffer.len());
+                    recording = false;
                     
-                    if max_amplitude > SILENCE_THRESHOLD {
-                        last_speech_time = Instant::now();
-                    } else {
-                        // Silence detecté
-                        if last_speech_time.elapsed().as_millis() > SILENCE_DURATION_MS && !buffer.is_empty() {
-                            info!("Silence auto-stop triggered");
-                            recording = false;
-                            
-                            let mut final_samples = process_buffer(&buffer, sample_rate);
-                            trim_silence(&mut final_samples, SILENCE_THRESHOLD);
-                            
-                            // Envoyer l'événement d'arrêt automatique
-                            let _ = event_tx.send(AudioEvent::AutoStopped(final_samples));
-                            
-                            buffer.clear(); // Reset buffer
-                        }
-                    }
+                    let recording = finish_recording(recording_id, &buffer, sample_rate, &config, &last_raw);
+                    
+                    // Envoyer l'événement d'arrêt automatique
+                    let _ = event_tx.send(AudioEvent::AutoStopped(recording));
+                    
+                    buffer.clear(); // Reset buffer
+                }
 
-                    if recording {
-                        buffer.extend_from_slice(&chunk);
-                    }
+                if recording {
+                    buffer.extend_from_slice(&chunk);
                 }
             }
-            Err(mpsc::RecvTimeoutError::Timeout) => continue,
-            Err(mpsc::RecvTimeoutError::Disconnected) => break,
         }
     }
+}
 
-    Ok(())
+// Liste des entrées disponibles, y compris les sources "monitor" quand
+// le serveur audio les expose comme des entrées (PulseAudio/PipeWire).
+pub fn list_input_devices() -> Vec<InputDevice> {
+    let host = cpal::default_host();
+    match host.input_devices() {
+        Ok(devices) => devices
+            .filter_map(|d| d.name().ok())
+            .map(|name| InputDevice {
+                is_monitor: name.to_lowercase().contains("monitor"),
+                name,
+            })
+            .collect(),
+        Err(e) => {
+            error!("Failed to enumerate input devices: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+// Durée vérifiée sur le buffer brut (nombre d'échantillons à la fréquence du périphérique)
+fn finish_recording(id: u64, buffer: &[f32], sample_rate: u32, config: &RecorderConfig, last_raw: &RawRecording) -> Recording {
+    if config.keep_raw {
+        *last_raw.lock().unwrap() = Some((buffer.to_vec(), sample_rate));
+    }
+    let min_samples = sample_rate as usize * config.min_duration_ms as usize / 1000;
+    if buffer.len() < min_samples {
+        info!("Recording #{} too short ({} samples < {}), discarded", id, buffer.len(), min_samples);
+        return Recording::TooShort;
+    }
+    let peak = buffer.iter().fold(0.0f32, |max, &x| max.max(x.abs()));
+    if peak < SILENT_FLOOR {
+        info!("Recording #{} is silent (peak {:.5} < {}), discarded", id, peak, SILENT_FLOOR);
+        return Recording::Silent;
+    }
+    let samples = finalize_buffer(buffer, sample_rate, config);
+    if samples.is_empty() {
+        info!("Recording #{}: no speech left after trimming silence, discarded", id);
+        return Recording::NoSpeech;
+    }
+    let stats = recording_stats(&samples);
+    info!("Recording #{}: {} samples after processing, {:?}", id, samples.len(), stats);
+    Recording::Samples { id, samples, stats }
+}
+
+// Buffer final envoyé à Whisper : resample, trim et filtres optionnels
+fn finalize_buffer(buffer: &[f32], sample_rate: u32, config: &RecorderConfig) -> Vec<f32> {
+    let mut samples = process_buffer(buffer, sample_rate);
+    trim_silence(&mut samples, SILENCE_THRESHOLD);
+    if config.pre_emphasis {
+        pre_emphasis(&mut samples, PRE_EMPHASIS_COEFF);
+    }
+    samples
 }
 
 // Helper pour traiter le buffer (resample)
@@ -190,8 +555,9 @@ fn process_buffer(buffer: &[f32], sample_rate: u32) -> Vec<f32> {
 fn build_stream<T>(
     device: &Device,
     config: &cpal::StreamConfig,
-    tx: mpsc::Sender<Vec<f32>>,
+    tx: mpsc::Sender<WorkerMsg>,
     channels: usize,
+    gain: Arc<AtomicU32>,
 ) -> Result<cpal::Stream>
 where
     T: SizedSample + Sample + Send + 'static,
@@ -200,12 +566,8 @@ where
     let stream = device.build_input_stream(
         config,
         move |data: &[T], _: &_| {
-            let mut output = Vec::with_capacity(data.len() / channels);
-            for frame in data.chunks(channels) {
-                let sum: f32 = frame.iter().map(|s| s.to_sample::<f32>()).sum();
-                output.push(sum / channels as f32);
-            }
-            let _ = tx.send(output);
+            let gain = f32::from_bits(gain.load(Ordering::Relaxed));
+            let _ = tx.send(WorkerMsg::Samples(downmix(data, channels, gain)));
         },
         |err| error!("Stream error: {}", err),
         None,
@@ -213,17 +575,141 @@ where
     Ok(stream)
 }
 
-fn get_preferred_config(device: &Device) -> Result<cpal::SupportedStreamConfig> {
-    let configs = device.supported_input_configs()?;
-    for config in configs {
-        if config.min_sample_rate().0 <= WHISPER_SAMPLE_RATE && config.max_sample_rate().0 >= WHISPER_SAMPLE_RATE {
-             return Ok(config.with_sample_rate(cpal::SampleRate(WHISPER_SAMPLE_RATE)));
+// Sortie de monitoring au même taux que la capture, sans resampling
+fn build_monitor_stream(sample_rate: u32, queue: MonitorQueue) -> Result<cpal::Stream> {
+    let host = cpal::default_host();
+    let device = host.default_output_device().ok_or(anyhow::anyhow!("No output device found"))?;
+
+    let config = device
+        .supported_output_configs()?
+        .find(|c| c.min_sample_rate().0 <= sample_rate && c.max_sample_rate().0 >= sample_rate)
+        .ok_or(anyhow::anyhow!("Output device does not support {} Hz", sample_rate))?
+        .with_sample_rate(cpal::SampleRate(sample_rate));
+    let channels = config.channels() as usize;
+    if channels == 0 {
+        return Err(anyhow::anyhow!("Output device reports 0 channels"));
+    }
+
+    let stream = match config.sample_format() {
+        cpal::SampleFormat::F32 => build_output_stream::<f32>(&device, &config.into(), queue, channels),
+        cpal::SampleFormat::I16 => build_output_stream::<i16>(&device, &config.into(), queue, channels),
+        cpal::SampleFormat::U16 => build_output_stream::<u16>(&device, &config.into(), queue, channels),
+        cpal::SampleFormat::I32 => build_output_stream::<i32>(&device, &config.into(), queue, channels),
+        _ => return Err(anyhow::anyhow!("Unsupported output sample format: {:?}", config.sample_format())),
+    }?;
+
+    stream.play()?;
+    Ok(stream)
+}
+
+fn build_output_stream<T>(
+    device: &Device,
+    config: &cpal::StreamConfig,
+    queue: MonitorQueue,
+    channels: usize,
+) -> Result<cpal::Stream>
+where
+    T: SizedSample + Sample + cpal::FromSample<f32> + Send + 'static,
+{
+    let stream = device.build_output_stream(
+        config,
+        move |data: &mut [T], _: &_| {
+            let mut queue = queue.lock().unwrap();
+            for frame in data.chunks_mut(channels) {
+                // Mono dupliqué sur tous les canaux, silence si la file est vide
+                let sample = T::from_sample(queue.pop_front().unwrap_or(0.0));
+                frame.fill(sample);
+            }
+        },
+        |err| error!("Monitor stream error: {}", err),
+        None,
+    )?;
+    Ok(stream)
+}
+
+// Fréquence demandée (ou 16kHz en auto) si le périphérique la supporte,
+// sinon 16kHz, sinon la config par défaut (resamplée ensuite).
+pub fn get_preferred_config(device: &Device, capture_rate: Option<u32>) -> Result<cpal::SupportedStreamConfig> {
+    let mut candidates = vec![capture_rate.unwrap_or(WHISPER_SAMPLE_RATE)];
+    if capture_rate.is_some() {
+        candidates.push(WHISPER_SAMPLE_RATE);
+    }
+
+    for rate in candidates {
+        let configs = device.supported_input_configs()?;
+        for config in configs {
+            if config.min_sample_rate().0 <= rate && config.max_sample_rate().0 >= rate {
+                 return Ok(config.with_sample_rate(cpal::SampleRate(rate)));
+            }
+        }
+        if capture_rate == Some(rate) {
+            warn!("Capture rate {} Hz not supported by the device, falling back", rate);
         }
     }
     Ok(device.default_input_config()?)
 }
 
-fn resample_simple(input: &[f32], in_rate: u32, out_rate: u32) -> Vec<f32> {
+// Entrelacé -> mono : moyenne des canaux, puis gain et clamp (évite les
+// artefacts d'écrêtage). Une trame finale incomplète est moyennée sur ses
+// seuls échantillons ; `channels == 0` est traité comme du mono.
+fn downmix<T>(data: &[T], channels: usize, gain: f32) -> Vec<f32>
+where
+    T: Sample,
+    f32: cpal::FromSample<T>,
+{
+    data.chunks(channels.max(1))
+        .map(|frame| {
+            let sum: f32 = frame.iter().map(|s| s.to_sample::<f32>()).sum();
+            (sum / frame.len() as f32 * gain).clamp(-1.0, 1.0)
+        })
+        .collect()
+}
+
+// WAV mono 32 bits flottant
+pub fn write_wav(path: &std::path::Path, samples: &[f32], sample_rate: u32) -> Result<()> {
+    let spec = hound::WavSpec {
+        channels: 1,
+        sample_rate,
+        bits_per_sample: 32,
+        sample_format: hound::SampleFormat::Float,
+    };
+    let mut writer = hound::WavWriter::create(path, spec)?;
+    for &sample in samples {
+        writer.write_sample(sample)?;
+    }
+    writer.finalize()?;
+    Ok(())
+}
+
+// Lit un WAV (entier ou flottant), mixé en mono et ramené à 16kHz pour Whisper
+pub fn read_wav(path: &std::path::Path) -> Result<Vec<f32>> {
+    let mut reader = hound::WavReader::open(path)?;
+    let spec = reader.spec();
+    let samples: Vec<f32> = match spec.sample_format {
+        hound::SampleFormat::Float => reader.samples::<f32>().collect::<Result<_, _>>()?,
+        hound::SampleFormat::Int => {
+            let scale = (1i64 << (spec.bits_per_sample - 1)) as f32;
+            reader
+                .samples::<i32>()
+                .map(|s| s.map(|s| s as f32 / scale))
+                .collect::<Result<_, _>>()?
+        }
+    };
+
+    let channels = spec.channels.max(1) as usize;
+    let mono: Vec<f32> = samples
+        .chunks(channels)
+        .map(|frame| frame.iter().sum::<f32>() / frame.len() as f32)
+        .collect();
+
+    if spec.sample_rate == WHISPER_SAMPLE_RATE {
+        Ok(mono)
+    } else {
+        Ok(resample_simple(&mono, spec.sample_rate, WHISPER_SAMPLE_RATE))
+    }
+}
+
+pub fn resample_simple(input: &[f32], in_rate: u32, out_rate: u32) -> Vec<f32> {
     let ratio = in_rate as f32 / out_rate as f32;
     let out_len = (input.len() as f32 / ratio) as usize;
     let mut output = Vec::with_capacity(out_len);
@@ -238,7 +724,29 @@ fn resample_simple(input: &[f32], in_rate: u32, out_rate: u32) -> Vec<f32> {
     output
 }
 
-fn trim_silence(samples: &mut Vec<f32>, threshold: f32) {
+// Les plosives démarrent doucement : tant que l'énergie décroît en remontant
+// le temps (donc monte vers `start`) et reste au-dessus du bruit de fond, on
+// recule le début. Borné par MAX_ATTACK_LOOKBACK et le début du buffer.
+fn attack_start(samples: &[f32], start: usize, threshold: f32) -> usize {
+    let rms = |window: &[f32]| (window.iter().map(|x| x * x).sum::<f32>() / window.len().max(1) as f32).sqrt();
+    let floor = threshold * ATTACK_FLOOR_RATIO;
+    let limit = start.saturating_sub(MAX_ATTACK_LOOKBACK);
+
+    let mut attack = start;
+    let mut level = rms(&samples[start..(start + ATTACK_WINDOW).min(samples.len())]);
+    while attack > limit {
+        let from = attack.saturating_sub(ATTACK_WINDOW).max(limit);
+        let previous = rms(&samples[from..attack]);
+        if previous >= level || previous < floor {
+            break;
+        }
+        attack = from;
+        level = previous;
+    }
+    attack
+}
+
+pub fn trim_silence(samples: &mut Vec<f32>, threshold: f32) {
     if samples.is_empty() { return; }
     let start = samples.iter().position(|&x| x.abs() > threshold).unwrap_or(0);
     let end = samples.iter().rposition(|&x| x.abs() > threshold).unwrap_or(samples.len() - 1);
@@ -246,8 +754,313 @@ fn trim_silence(samples: &mut Vec<f32>, threshold: f32) {
         samples.clear();
     } else {
         let padding = 3200;
-        let start_pad = start.saturating_sub(padding);
+        let start_pad = attack_start(samples, start, threshold).saturating_sub(padding);
         let end_pad = (end + padding).min(samples.len());
         *samples = samples[start_pad..end_pad].to_vec();
     }
-}
\ No newline at end of file
+}
+
+// Pré-accentuation : y[n] = x[n] - coeff * x[n-1], le premier échantillon est inchangé.
+// Parcours à l'envers pour toujours utiliser l'échantillon d'origine x[n-1].
+pub fn pre_emphasis(samples: &mut [f32], coeff: f32) {
+    for i in (1..samples.len()).rev() {
+        samples[i] -= coeff * samples[i - 1];
+    }
+}
+
+// Diagnostic du buffer final (16 kHz), pour comprendre une mauvaise transcription
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RecordingStats {
+    pub duration_secs: f32,
+    pub peak: f32,
+    pub rms: f32,
+    // Écart entre le niveau des trames de parole et celui des trames calmes.
+    // `None` sans trame de chaque sorte (ou bruit de fond nul).
+    pub snr_db: Option<f32>,
+    // Part des échantillons à ±CLIPPING_LEVEL ou au-delà, en %
+    pub clipping_percent: f32,
+    // Durée des trames classées "parole" par le VAD
+    pub speech_secs: f32,
+}
+
+pub fn recording_stats(samples: &[f32]) -> RecordingStats {
+    if samples.is_empty() {
+        return RecordingStats::default();
+    }
+    let rate = WHISPER_SAMPLE_RATE as f32;
+    let rms_of = |frame: &[f32]| (frame.iter().map(|x| x * x).sum::<f32>() / frame.len() as f32).sqrt();
+
+    // Niveau moyen des trames de parole et des trames calmes, d'après le VAD
+    let frame_len = (WHISPER_SAMPLE_RATE / 1000 * VAD_FRAME_MS) as usize;
+    let mut vad = Vad::new(WHISPER_SAMPLE_RATE, SILENCE_DURATION_MS);
+    let (mut speech_rms, mut speech_frames, mut noise_rms, mut noise_frames) = (0.0, 0, 0.0, 0);
+    let mut speech_samples = 0;
+    for frame in samples.chunks(frame_len) {
+        if vad.process(frame) == VadDecision::Speech {
+            speech_rms += rms_of(frame);
+            speech_frames += 1;
+            speech_samples += frame.len();
+        } else {
+            noise_rms += rms_of(frame);
+            noise_frames += 1;
+        }
+    }
+    let snr_db = (speech_frames > 0 && noise_frames > 0 && noise_rms > 0.0).then(|| {
+        let speech = speech_rms / speech_frames as f32;
+        let noise = noise_rms / noise_frames as f32;
+        20.0 * (speech / noise).log10()
+    });
+
+    let clipped = samples.iter().filter(|x| x.abs() >= CLIPPING_LEVEL).count();
+    RecordingStats {
+        duration_secs: samples.len() as f32 / rate,
+        peak: samples.iter().fold(0.0f32, |max, &x| max.max(x.abs())),
+        rms: rms_of(samples),
+        snr_db,
+        clipping_percent: clipped as f32 * 100.0 / samples.len() as f32,
+        speech_secs: speech_samples as f32 / rate,
+    }
+}
+
+// Découpe un buffer 16kHz aux silences d'au moins `min_silence_ms`, en coupant
+// au milieu du silence. Même VAD que l'auto-stop, appliqué par trame.
+// Sans silence suffisant, le buffer est renvoyé en un seul morceau.
+pub fn split_on_silence(samples: &[f32], min_silence_ms: u32) -> Vec<&[f32]> {
+    let frame_len = (WHISPER_SAMPLE_RATE / 1000 * VAD_FRAME_MS) as usize;
+    let min_silent_len = (WHISPER_SAMPLE_RATE / 1000 * min_silence_ms) as usize;
+
+    let mut chunks = Vec::new();
+    let mut chunk_start = 0;
+    let mut silence_start: Option<usize> = None;
+    let mut vad = Vad::new(WHISPER_SAMPLE_RATE, min_silence_ms);
+
+    for (i, frame) in samples.chunks(frame_len).enumerate() {
+        let pos = i * frame_len;
+
+        if vad.process(frame) == VadDecision::Speech {
+            if let Some(start) = silence_start.take() {
+                if pos - start >= min_silent_len && start > chunk_start {
+                    let cut = start + (pos - start) / 2;
+                    chunks.push(&samples[chunk_start..cut]);
+                    chunk_start = cut;
+                }
+            }
+        } else if silence_start.is_none() {
+            silence_start = Some(pos);
+        }
+    }
+
+    if chunk_start < samples.len() {
+        chunks.push(&samples[chunk_start..]);
+    }
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 30 ms à 16 kHz
+    const FRAME: usize = 480;
+
+    fn level(amplitude: f32, len: usize) -> Vec<f32> {
+        vec![amplitude; len]
+    }
+
+    #[test]
+    fn vad_detects_onset_above_threshold() {
+        let mut vad = Vad::new(WHISPER_SAMPLE_RATE, 500);
+        assert_eq!(vad.process(&level(SILENCE_THRESHOLD * 0.5, FRAME)), VadDecision::Silence);
+        assert_eq!(vad.process(&level(SILENCE_THRESHOLD * 2.0, FRAME)), VadDecision::Speech);
+    }
+
+    #[test]
+    fn vad_holds_speech_until_release_threshold() {
+        let between = (SILENCE_THRESHOLD + SILENCE_RELEASE_THRESHOLD) / 2.0;
+        let mut vad = Vad::new(WHISPER_SAMPLE_RATE, 500);
+        // Entre les deux seuils : pas de démarrage...
+        assert_eq!(vad.process(&level(between, FRAME)), VadDecision::Silence);
+        // ...mais maintien une fois la parole détectée
+        assert_eq!(vad.process(&level(SILENCE_THRESHOLD * 2.0, FRAME)), VadDecision::Speech);
+        assert_eq!(vad.process(&level(between, FRAME)), VadDecision::Speech);
+        assert_eq!(vad.process(&level(SILENCE_RELEASE_THRESHOLD * 0.5, FRAME)), VadDecision::Silence);
+        assert_eq!(vad.process(&level(between, FRAME)), VadDecision::Silence);
+    }
+
+    #[test]
+    fn vad_auto_stops_after_configured_silence() {
+        let mut vad = Vad::new(WHISPER_SAMPLE_RATE, 500);
+        assert_eq!(vad.process(&level(0.5, FRAME)), VadDecision::Speech);
+        // 500 ms = 8000 échantillons : l'arrêt tombe sur la 17e trame (8160)
+        for _ in 0..16 {
+            assert_eq!(vad.process(&level(0.0, FRAME)), VadDecision::Silence);
+        }
+        assert_eq!(vad.process(&level(0.0, FRAME)), VadDecision::AutoStop);
+    }
+
+    #[test]
+    fn vad_timing_does_not_depend_on_chunk_size() {
+        let mut vad = Vad::new(WHISPER_SAMPLE_RATE, 500);
+        assert_eq!(vad.process(&level(0.5, 100)), VadDecision::Speech);
+        for _ in 0..8 {
+            assert_eq!(vad.process(&level(0.0, 1000)), VadDecision::Silence);
+        }
+        assert_eq!(vad.process(&level(0.0, 1)), VadDecision::AutoStop);
+    }
+
+    #[test]
+    fn vad_waits_for_speech_before_auto_stop() {
+        let mut vad = Vad::new(WHISPER_SAMPLE_RATE, 500);
+        for _ in 0..100 {
+            assert_eq!(vad.process(&level(0.0, FRAME)), VadDecision::Silence);
+        }
+        assert_eq!(vad.process(&level(0.5, FRAME)), VadDecision::Speech);
+
+        vad.reset();
+        for _ in 0..100 {
+            assert_eq!(vad.process(&level(0.0, FRAME)), VadDecision::Silence);
+        }
+    }
+
+    #[test]
+    fn pre_emphasis_keeps_first_sample() {
+        let mut samples = vec![0.5, 0.5, 1.0, 0.0];
+        pre_emphasis(&mut samples, 0.5);
+        assert_eq!(samples, vec![0.5, 0.25, 0.75, -0.5]);
+    }
+
+    #[test]
+    fn pre_emphasis_uses_original_previous_sample() {
+        let mut samples = vec![1.0; 4];
+        pre_emphasis(&mut samples, PRE_EMPHASIS_COEFF);
+        assert_eq!(samples[0], 1.0);
+        for sample in &samples[1..] {
+            assert!((sample - (1.0 - PRE_EMPHASIS_COEFF)).abs() < 1e-6, "got {}", sample);
+        }
+    }
+
+    #[test]
+    fn pre_emphasis_ignores_empty_input() {
+        let mut samples: Vec<f32> = Vec::new();
+        pre_emphasis(&mut samples, PRE_EMPHASIS_COEFF);
+        assert!(samples.is_empty());
+    }
+
+    fn assert_close(actual: &[f32], expected: &[f32]) {
+        assert_eq!(actual.len(), expected.len(), "{:?} != {:?}", actual, expected);
+        for (a, e) in actual.iter().zip(expected) {
+            assert!((a - e).abs() < 1e-6, "{:?} != {:?}", actual, expected);
+        }
+    }
+
+    #[test]
+    fn downmix_passes_mono_through() {
+        assert_close(&downmix(&[0.1f32, -0.2, 0.3], 1, 1.0), &[0.1, -0.2, 0.3]);
+    }
+
+    #[test]
+    fn downmix_averages_stereo_frames() {
+        assert_close(&downmix(&[0.2f32, 0.4, -0.5, 0.5], 2, 1.0), &[0.3, 0.0]);
+    }
+
+    #[test]
+    fn downmix_averages_partial_frame_over_its_length() {
+        assert_close(&downmix(&[0.2f32, 0.4, 0.6], 2, 1.0), &[0.3, 0.6]);
+    }
+
+    #[test]
+    fn downmix_treats_zero_channels_as_mono() {
+        assert_close(&downmix(&[0.1f32, 0.2], 0, 1.0), &[0.1, 0.2]);
+    }
+
+    #[test]
+    fn downmix_applies_gain_and_clamps() {
+        assert_close(&downmix(&[0.1f32, 0.2], 2, 2.0), &[0.3]);
+        assert_close(&downmix(&[0.6f32, 0.8, -0.6, -0.8], 2, 2.0), &[1.0, -1.0]);
+    }
+
+    #[test]
+    fn downmix_converts_integer_samples() {
+        let mixed = downmix(&[i16::MAX, i16::MAX, 0, 0], 2, 1.0);
+        assert!((mixed[0] - 1.0).abs() < 1e-3, "{:?}", mixed);
+        assert_eq!(mixed[1], 0.0);
+    }
+
+    // `len` échantillons montant linéairement de `from` à `to`
+    fn ramp(from: f32, to: f32, len: usize) -> Vec<f32> {
+        (0..len).map(|i| from + (to - from) * (i + 1) as f32 / len as f32).collect()
+    }
+
+    #[test]
+    fn attack_start_moves_back_over_ramp_and_stops_at_noise_floor() {
+        let mut samples = level(0.0, 4000);
+        samples.extend(ramp(0.0, SILENCE_THRESHOLD, 1600));
+        samples.extend(level(0.5, 2000));
+        let start = 5600;
+
+        let attack = attack_start(&samples, start, SILENCE_THRESHOLD);
+        assert!(attack < start - ATTACK_WINDOW, "start not moved back: {}", attack);
+        // Le début de la rampe est sous le bruit de fond, le silence n'est pas repris
+        assert!((4000..=4000 + 2 * ATTACK_WINDOW).contains(&attack), "got {}", attack);
+    }
+
+    #[test]
+    fn attack_start_keeps_sharp_onset() {
+        let mut samples = level(0.0, 2000);
+        samples.extend(level(0.5, 2000));
+        assert_eq!(attack_start(&samples, 2000, SILENCE_THRESHOLD), 2000);
+    }
+
+    #[test]
+    fn attack_start_respects_max_lookback() {
+        let mut samples = ramp(0.002, SILENCE_THRESHOLD, 10000);
+        samples.extend(level(0.5, 2000));
+        assert_eq!(attack_start(&samples, 10000, SILENCE_THRESHOLD), 10000 - MAX_ATTACK_LOOKBACK);
+    }
+
+    #[test]
+    fn attack_start_stops_at_buffer_start() {
+        let mut samples = ramp(0.002, SILENCE_THRESHOLD, 800);
+        samples.extend(level(0.5, 2000));
+        assert_eq!(attack_start(&samples, 800, SILENCE_THRESHOLD), 0);
+    }
+
+    #[test]
+    fn recording_stats_counts_clipping_on_both_sides() {
+        let mut samples = level(0.5, FRAME * 10);
+        samples[..24].fill(CLIPPING_LEVEL);
+        samples[24..48].fill(-1.0);
+        // Juste sous le seuil : pas compté
+        samples[48..96].fill(CLIPPING_LEVEL - 0.01);
+        let stats = recording_stats(&samples);
+        assert!((stats.clipping_percent - 1.0).abs() < 1e-4, "got {}", stats.clipping_percent);
+        assert_eq!(stats.peak, 1.0);
+    }
+
+    #[test]
+    fn recording_stats_has_no_snr_without_noise_frames() {
+        let stats = recording_stats(&level(0.5, FRAME * 10));
+        assert!(stats.snr_db.is_none());
+        assert!((stats.speech_secs - 0.3).abs() < 1e-4, "got {}", stats.speech_secs);
+        assert!((stats.duration_secs - 0.3).abs() < 1e-4, "got {}", stats.duration_secs);
+    }
+
+    #[test]
+    fn recording_stats_measures_snr_and_speech_duration() {
+        let mut samples = level(0.5, FRAME * 10);
+        samples.extend(level(0.001, FRAME * 10));
+        let stats = recording_stats(&samples);
+        // 20 * log10(0.5 / 0.001)
+        let snr = stats.snr_db.expect("speech and noise frames");
+        assert!((snr - 53.98).abs() < 0.01, "got {}", snr);
+        assert!((stats.speech_secs - 0.3).abs() < 1e-4, "got {}", stats.speech_secs);
+        assert!((stats.duration_secs - 0.6).abs() < 1e-4, "got {}", stats.duration_secs);
+    }
+
+    #[test]
+    fn recording_stats_of_empty_buffer_is_zero() {
+        let stats = recording_stats(&[]);
+        assert_eq!(stats.duration_secs, 0.0);
+        assert!(stats.snr_db.is_none());
+    }
+}
@@ -0,0 +1,200 @@
+use crate::config::Settings;
+use nspeech::transcription::TranscriptionManager;
+use anyhow::{anyhow, Result};
+use log::{info, warn};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use walkdir::WalkDir;
+
+// Transcription d'un dossier en ligne de commande : `nspeech --batch DIR [--jobs N]`.
+// Chaque fichier WAV (sous-dossiers compris) donne un `.txt` à côté de lui.
+pub struct BatchOptions {
+    pub dir: PathBuf,
+    // Fichiers traités en parallèle, un moteur Whisper chargé par worker
+    pub jobs: usize,
+}
+
+impl BatchOptions {
+    // `None` sans `--batch` : lancement normal de l'interface
+    pub fn from_args(args: &[String]) -> Option<Result<Self>> {
+        let index = args.iter().position(|arg| arg == "--batch")?;
+        Some(Self::parse(args, index))
+    }
+
+    fn parse(args: &[String], batch_index: usize) -> Result<Self> {
+        let dir = args
+            .get(batch_index + 1)
+            .filter(|dir| !dir.starts_with("--"))
+            .ok_or(anyhow!("--batch needs a directory"))?;
+        let jobs = match args.iter().position(|arg| arg == "--jobs") {
+            Some(index) => args
+                .get(index + 1)
+                .and_then(|n| n.parse::<usize>().ok())
+                .filter(|&n| n > 0)
+                .ok_or(anyhow!("--jobs needs a positive number"))?,
+            None => 1,
+        };
+        Ok(Self { dir: PathBuf::from(dir), jobs })
+    }
+}
+
+// Renvoie le nombre de fichiers en échec. Les résultats sont rapportés dans
+// l'ordre des fichiers, quel que soit l'ordre dans lequel les workers finissent.
+pub fn run(options: BatchOptions) -> Result<usize> {
+    let files = wav_files(&options.dir)?;
+    if files.is_empty() {
+        return Err(anyhow!("No WAV file found in {:?}", options.dir));
+    }
+
+    let settings = Settings::load();
+    // Mêmes réglages de transcription que l'interface
+    let new_transcriber = || {
+        let transcriber = TranscriptionManager::new(&TranscriptionManager::default_model_dir(), &settings.model);
+        transcriber.set_language(settings.language.clone());
+        transcriber.set_post_processing(settings.clean_up_text);
+        transcriber.set_profanity_filter(settings.profanity_filter);
+        transcriber.set_split_on_silence(settings.split_on_silence);
+        transcriber.set_replacements(settings.replacements.clone());
+        if let Some(words) = settings.profanity_words.clone() {
+            transcriber.set_profanity_words(words);
+        }
+        transcriber
+    };
+
+    // Le premier moteur est chargé seul : il télécharge le modèle si besoin,
+    // sans que plusieurs workers se disputent le fichier
+    let first = new_transcriber();
+    first.load_model()?;
+
+    // Mémoire limitée : moins de modèles chargés en même temps que demandé
+    let mut jobs = options.jobs.min(files.len());
+    if let Some(spare) = first.spare_model_slots() {
+        if jobs > spare + 1 {
+            warn!("Not enough memory for {} models, using {} jobs", jobs, spare + 1);
+            jobs = spare + 1;
+        }
+    }
+    info!("Batch: {} files in {:?}, {} jobs", files.len(), options.dir, jobs);
+
+    let files = Arc::new(files);
+    let next = Arc::new(AtomicUsize::new(0));
+    let (tx, rx) = mpsc::channel();
+    let workers: Vec<_> = std::iter::once(first)
+        .chain((1..jobs).map(|_| new_transcriber()))
+        .map(|transcriber| {
+            let files = files.clone();
+            let next = next.clone();
+            let tx = tx.clone();
+            thread::spawn(move || {
+                // Un worker qui ne peut pas charger son modèle laisse la file aux autres
+                if !transcriber.is_loaded() {
+                    if let Err(e) = transcriber.load_model() {
+                        warn!("Batch worker stopped, model not loaded: {}", e);
+                        return;
+                    }
+                }
+                loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(path) = files.get(index) else {
+                        break;
+                    };
+                    if tx.send((index, transcribe_to_text(&transcriber, path))).is_err() {
+                        break;
+                    }
+                }
+            })
+        })
+        .collect();
+    drop(tx);
+
+    let mut results: Vec<Option<Result<PathBuf>>> = files.iter().map(|_| None).collect();
+    for (index, result) in rx {
+        results[index] = Some(result);
+    }
+    for worker in workers {
+        let _ = worker.join();
+    }
+
+    let mut failed = 0;
+    for (path, result) in files.iter().zip(results) {
+        match result {
+            Some(Ok(output)) => println!("ok     {} -> {}", path.display(), output.display()),
+            Some(Err(e)) => {
+                failed += 1;
+                println!("error  {}: {}", path.display(), e);
+            }
+            None => {
+                failed += 1;
+                println!("error  {}: not processed", path.display());
+            }
+        }
+    }
+    println!("{} transcribed, {} failed", files.len() - failed, failed);
+    Ok(failed)
+}
+
+// Fichiers WAV du dossier et de ses sous-dossiers, triés par chemin
+fn wav_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for entry in WalkDir::new(dir).sort_by_file_name() {
+        let entry = entry?;
+        let is_wav = entry.path().extension().is_some_and(|ext| ext.eq_ignore_ascii_case("wav"));
+        if entry.file_type().is_file() && is_wav {
+            files.push(entry.into_path());
+        }
+    }
+    Ok(files)
+}
+
+fn transcribe_to_text(transcriber: &TranscriptionManager, path: &Path) -> Result<PathBuf> {
+    let transcription = transcriber.transcribe_file(path)?;
+    let output = path.with_extension("txt");
+    std::fs::write(&output, format!("{}\n", transcription.text.trim()))?;
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn parse_reads_dir_and_jobs() {
+        let options = BatchOptions::from_args(&args(&["nspeech", "--batch", "audio", "--jobs", "3"])).unwrap().unwrap();
+        assert_eq!(options.dir, PathBuf::from("audio"));
+        assert_eq!(options.jobs, 3);
+    }
+
+    #[test]
+    fn parse_defaults_to_one_job() {
+        let options = BatchOptions::from_args(&args(&["nspeech", "--batch", "audio"])).unwrap().unwrap();
+        assert_eq!(options.jobs, 1);
+    }
+
+    #[test]
+    fn parse_without_batch_launches_the_gui() {
+        assert!(BatchOptions::from_args(&args(&["nspeech", "--jobs", "2"])).is_none());
+    }
+
+    #[test]
+    fn parse_rejects_missing_dir() {
+        assert!(BatchOptions::from_args(&args(&["nspeech", "--batch"])).unwrap().is_err());
+        assert!(BatchOptions::from_args(&args(&["nspeech", "--batch", "--jobs", "2"])).unwrap().is_err());
+    }
+
+    #[test]
+    fn parse_rejects_zero_jobs() {
+        assert!(BatchOptions::from_args(&args(&["nspeech", "--batch", "audio", "--jobs", "0"])).unwrap().is_err());
+    }
+
+    #[test]
+    fn parse_rejects_jobs_without_value() {
+        assert!(BatchOptions::from_args(&args(&["nspeech", "--batch", "audio", "--jobs"])).unwrap().is_err());
+        assert!(BatchOptions::from_args(&args(&["nspeech", "--batch", "audio", "--jobs", "many"])).unwrap().is_err());
+    }
+}
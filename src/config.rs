@@ -0,0 +1,175 @@
+use nspeech::audio::RecorderConfig;
+use nspeech::transcription::{Replacement, DEFAULT_LANGUAGE, DEFAULT_MODEL};
+use anyhow::{anyhow, Result};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+// Que faire d'un enregistrement en cours à la fermeture de la fenêtre
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CloseAction {
+    // Transcrire puis fermer (le texte rejoint la session et le journal)
+    #[default]
+    Transcribe,
+    // Sauvegarder l'audio en WAV sans attendre la transcription
+    SaveRaw,
+}
+
+// Réglages lus depuis ~/.config/nspeech/config.json
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    // Dictionnaire personnel appliqué après le nettoyage du texte
+    pub replacements: Vec<Replacement>,
+    // Liste personnalisée du filtre de grossièretés (liste par défaut si absente)
+    pub profanity_words: Option<Vec<String>>,
+    // Dossier du journal : chaque transcription y est ajoutée (désactivé si absent)
+    pub autosave_dir: Option<PathBuf>,
+    pub close_action: CloseAction,
+    // Diagnostic : garde le buffer brut du micro et affiche "Save Raw"
+    pub keep_raw_audio: bool,
+    // Copie automatique de chaque transcription dans le presse-papiers
+    pub auto_copy: bool,
+    // Variante du modèle Whisper, ex. "base-q5_1"
+    pub model: String,
+    // Mode compact : bouton d'enregistrement et vumètre seulement
+    pub minimal_mode: bool,
+    // Options de la fenêtre de préférences, reprises au démarrage.
+    // Périphérique d'entrée par nom (`None` = défaut du système)
+    pub input_device: Option<String>,
+    // Fréquence de capture forcée (`None` = 16kHz si supporté)
+    pub capture_rate: Option<u32>,
+    pub gain: f32,
+    pub min_duration_ms: u32,
+    pub auto_stop: bool,
+    pub monitor: bool,
+    pub pre_emphasis: bool,
+    // Code Whisper de la langue (`None` = détection automatique)
+    pub language: Option<String>,
+    pub split_on_silence: bool,
+    pub clean_up_text: bool,
+    pub profanity_filter: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        let recorder = RecorderConfig::default();
+        Self {
+            replacements: Vec::new(),
+            profanity_words: None,
+            autosave_dir: None,
+            close_action: CloseAction::default(),
+            keep_raw_audio: false,
+            auto_copy: true,
+            model: DEFAULT_MODEL.to_string(),
+            minimal_mode: false,
+            input_device: None,
+            capture_rate: recorder.capture_rate,
+            gain: recorder.gain,
+            min_duration_ms: recorder.min_duration_ms,
+            auto_stop: recorder.auto_stop,
+            monitor: recorder.monitor,
+            pre_emphasis: recorder.pre_emphasis,
+            language: Some(DEFAULT_LANGUAGE.to_string()),
+            split_on_silence: false,
+            clean_up_text: true,
+            profanity_filter: false,
+        }
+    }
+}
+
+impl Settings {
+    pub fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("nspeech").join("config.json"))
+    }
+
+    // Fichier absent ou invalide : réglages par défaut. Un fichier invalide
+    // (souvent édité à la main) est mis de côté en config.json.bak pour que
+    // la prochaine sauvegarde ne l'écrase pas.
+    pub fn load() -> Self {
+        let Some(path) = Self::path() else {
+            return Self::default();
+        };
+
+        match std::fs::read_to_string(&path) {
+            Ok(content) => match serde_json::from_str(&content) {
+                Ok(settings) => {
+                    info!("Settings loaded from {:?}", path);
+                    settings
+                }
+                Err(e) => {
+                    warn!("Invalid settings file {:?}: {}", path, e);
+                    let backup = path.with_extension("json.bak");
+                    match std::fs::rename(&path, &backup) {
+                        Ok(()) => warn!("Invalid settings moved to {:?}", backup),
+                        Err(e) => warn!("Failed to back up invalid settings to {:?}: {}", backup, e),
+                    }
+                    Self::default()
+                }
+            },
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = Self::path().ok_or(anyhow!("No config directory"))?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        // Fichier temporaire puis renommage : une fermeture pendant
+        // l'écriture ne laisse pas de config.json tronqué
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&tmp, &path)?;
+        Ok(())
+    }
+}
+
+// Ajoute une transcription horodatée au fichier du jour (YYYY-MM-DD.txt)
+pub fn append_autosave(dir: &Path, text: &str) -> Result<PathBuf> {
+    std::fs::create_dir_all(dir)?;
+    let now = chrono::Local::now();
+    let path = dir.join(format!("{}.txt", now.format("%Y-%m-%d")));
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(&path)?;
+    writeln!(file, "[{}] {}", now.format("%H:%M:%S"), text)?;
+    Ok(path)
+}
+
+// Enregistrements sauvegardés à la fermeture, un fichier horodaté par enregistrement
+pub fn recording_path() -> Result<PathBuf> {
+    let dir = dirs::data_dir().ok_or(anyhow!("No data directory"))?.join("nspeech").join("recordings");
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir.join(format!("{}.wav", chrono::Local::now().format("%Y-%m-%d_%H%M%S"))))
+}
+
+// Dernière transcription, restaurée au démarrage
+fn session_path() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("nspeech").join("session.txt"))
+}
+
+// Fichier absent ou illisible : session vide
+pub fn load_session() -> String {
+    session_path()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_session(text: &str) -> Result<()> {
+    let path = session_path().ok_or(anyhow!("No data directory"))?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, text)?;
+    Ok(())
+}
+
+pub fn clear_session() -> Result<()> {
+    if let Some(path) = session_path() {
+        if path.exists() {
+            std::fs::remove_file(path)?;
+        }
+    }
+    Ok(())
+}
@@ -1,23 +1,444 @@
 use gtk4::prelude::*;
-use gtk4::{Application, ApplicationWindow, Button, Box, Orientation, TextView, ScrolledWindow, TextBuffer};
-use std::sync::{Arc, Mutex};
+use gtk4::{Application, ApplicationWindow, Button, Box, CheckButton, DropDown, Label, LevelBar, Orientation, ProgressBar, Scale, SpinButton, TextView, ScrolledWindow, TextBuffer, ToggleButton};
+use gtk4_layer_shell::{Edge, KeyboardMode, Layer, LayerShell};
+use log::{error, info, warn};
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::sync::{mpsc, Arc, Mutex};
 use std::thread;
-use crate::audio::{AudioRecorder, AudioEvent};
-use crate::transcription::TranscriptionManager;
+use std::time::Duration;
+use crate::config::{self, CloseAction, Settings};
+use nspeech::audio::{self, AudioRecorder, AudioEvent, InputDevice, Recording, RecorderConfig, RecordingStats, MAX_GAIN, MIN_GAIN};
+use nspeech::transcription::{export_transcript, ExportFormat, Segment, Transcription, TranscriptionManager, MODELS};
+
+// Langues proposées dans le sélecteur (libellé, code Whisper).
+// `None` = détection automatique.
+const LANGUAGES: &[(&str, Option<&str>)] = &[
+    ("Auto-detect", None),
+    ("Français", Some("fr")),
+    ("English", Some("en")),
+    ("Deutsch", Some("de")),
+    ("Español", Some("es")),
+    ("Italiano", Some("it")),
+];
+const DEFAULT_LANGUAGE_INDEX: u32 = 1;
+
+// Transcription dans un thread, résultat et progression renvoyés à l'UI
+// `id` : identifiant de l'enregistrement, repris dans les logs
+fn spawn_transcription(transcriber: TranscriptionManager, id: u64, samples: Arc<Vec<f32>>, sender: async_channel::Sender<AppMsg>) {
+    thread::spawn(move || {
+        info!("Recording #{}: transcribing {} samples", id, samples.len());
+        let sender_progress = sender.clone();
+        let on_progress = move |fraction| {
+            let _ = sender_progress.send_blocking(AppMsg::TranscriptionProgress(fraction));
+        };
+        match transcriber.transcribe_with_progress(&samples, on_progress) {
+            Ok(transcription) => {
+                info!("Recording #{}: transcribed ({} characters)", id, transcription.text.len());
+                let _ = sender.send_blocking(AppMsg::TranscriptionSuccess(transcription));
+            }
+            Err(e) => {
+                error!("Recording #{}: transcription failed: {}", id, e);
+                let _ = sender.send_blocking(AppMsg::TranscriptionError(e.to_string()));
+            }
+        }
+    });
+}
+
+// Chemin de fichier WAV existant dans le texte du presse-papiers
+// (chemin brut ou URI file:// copiée depuis un gestionnaire de fichiers)
+fn clipboard_audio_path(text: &str) -> Option<std::path::PathBuf> {
+    let text = text.trim().lines().next()?.trim();
+    let path = match url::Url::parse(text) {
+        Ok(url) if url.scheme() == "file" => url.to_file_path().ok()?,
+        _ => std::path::PathBuf::from(text),
+    };
+    let is_wav = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("wav"));
+    (is_wav && path.is_file()).then_some(path)
+}
+
+// Section de la fenêtre de préférences : titre en gras et note facultative
+// (ce qui s'applique en direct ou demande de rouvrir l'entrée, etc.)
+fn preferences_section(title: &str, note: Option<&str>) -> Box {
+    let section = Box::new(Orientation::Vertical, 6);
+    let header = Label::new(None);
+    header.set_markup(&format!("<b>{}</b>", title));
+    header.set_xalign(0.0);
+    section.append(&header);
+    if let Some(note) = note {
+        let note = Label::new(Some(note));
+        note.set_xalign(0.0);
+        note.set_wrap(true);
+        note.add_css_class("dim-label");
+        section.append(&note);
+    }
+    section
+}
+
+// Ligne "libellé : widget" des préférences
+fn preferences_row(label: &str, widget: &impl IsA<gtk4::Widget>) -> Box {
+    let row = Box::new(Orientation::Horizontal, 10);
+    let label = Label::new(Some(label));
+    label.set_xalign(0.0);
+    label.set_hexpand(true);
+    row.append(&label);
+    row.append(widget);
+    row
+}
+
+// Plages (en caractères) des segments dans `text`. Ils sont retrouvés par leurs
+// lettres et chiffres dans l'ordre : le nettoyage ne touche qu'aux espaces, à la
+// ponctuation et à la casse. Un segment introuvable est simplement ignoré.
+// La recherche se fait dans le texte réduit à ses lettres et chiffres en
+// minuscules (`str::find`, linéaire) : un segment absent ne coûte qu'un parcours.
+fn segment_ranges(text: &str, segments: &[Segment]) -> Vec<(usize, i32, i32)> {
+    let chars: Vec<char> = text.chars().collect();
+    // Position dans `chars` de chaque octet de `letters`
+    let mut letters = String::new();
+    let mut origin = Vec::new();
+    for (i, c) in chars.iter().enumerate().filter(|(_, c)| c.is_alphanumeric()) {
+        for lower in c.to_lowercase() {
+            letters.push(lower);
+            origin.extend(std::iter::repeat_n(i, lower.len_utf8()));
+        }
+    }
+
+    let mut ranges = Vec::new();
+    let mut pos = 0;
+    for (index, segment) in segments.iter().enumerate() {
+        let key: String = segment.text.chars().filter(|c| c.is_alphanumeric()).flat_map(char::to_lowercase).collect();
+        if key.is_empty() {
+            continue;
+        }
+        let Some(found) = letters[pos..].find(&key).map(|offset| pos + offset) else {
+            continue;
+        };
+        let start = origin[found];
+        let mut end = origin[found + key.len() - 1] + 1;
+        // La ponctuation collée au dernier mot fait partie du segment, y compris
+        // après l'espace insécable du français ("va\u{a0}?")
+        while end < chars.len() && (chars[end] == '\u{a0}' || !chars[end].is_whitespace()) && !chars[end].is_alphanumeric() {
+            end += 1;
+        }
+        ranges.push((index, start as i32, end as i32));
+        pos = found + key.len();
+    }
+    ranges
+}
+
+// Un tag anonyme par segment : il suit le texte, retrouvé depuis le curseur
+fn tag_segments(buffer: &TextBuffer, tags: &RefCell<Vec<(gtk4::TextTag, Segment)>>, offset: i32, text: &str, segments: &[Segment]) {
+    for (index, start, end) in segment_ranges(text, segments) {
+        let tag = gtk4::TextTag::new(None);
+        buffer.tag_table().add(&tag);
+        buffer.apply_tag(&tag, &buffer.iter_at_offset(offset + start), &buffer.iter_at_offset(offset + end));
+        tags.borrow_mut().push((tag, segments[index].clone()));
+    }
+}
+
+fn clear_segment_tags(buffer: &TextBuffer, tags: &RefCell<Vec<(gtk4::TextTag, Segment)>>) {
+    for (tag, _) in tags.borrow_mut().drain(..) {
+        buffer.tag_table().remove(&tag);
+    }
+}
+
+// Retire les `count` derniers tags (résultat remplacé par "Retry")
+fn remove_last_segment_tags(buffer: &TextBuffer, tags: &RefCell<Vec<(gtk4::TextTag, Segment)>>, count: usize) {
+    let mut tags = tags.borrow_mut();
+    let from = tags.len().saturating_sub(count);
+    for (tag, _) in tags.drain(from..) {
+        buffer.tag_table().remove(&tag);
+    }
+}
+
+// Panneau de diagnostic : mesures du dernier enregistrement et conseil éventuel
+fn stats_text(stats: &RecordingStats) -> String {
+    let snr = stats.snr_db.map_or("n/a".to_string(), |db| format!("{:.0} dB", db));
+    let mut text = format!(
+        "Duration: {:.1} s (speech {:.1} s)\nPeak: {:.2}   RMS: {:.3}\nEstimated SNR: {}   Clipping: {:.2} %",
+        stats.duration_secs, stats.speech_secs, stats.peak, stats.rms, snr, stats.clipping_percent
+    );
+    let hint = if stats.clipping_percent > 0.1 {
+        Some("Clipping: lower the gain or move away from the microphone.")
+    } else if stats.peak < 0.1 {
+        Some("Quiet signal: raise the gain or move closer to the microphone.")
+    } else if stats.snr_db.is_some_and(|db| db < 10.0) {
+        Some("Noisy: background noise is close to the speech level.")
+    } else {
+        None
+    };
+    if let Some(hint) = hint {
+        text.push('\n');
+        text.push_str(hint);
+    }
+    text
+}
+
+// Barre de progression : pulsation tant qu'aucune progression réelle n'est connue
+fn start_progress_pulse(bar: &ProgressBar, pulse_source: &Rc<RefCell<Option<glib::SourceId>>>) {
+    stop_progress_pulse(pulse_source);
+    bar.set_fraction(0.0);
+    bar.set_visible(true);
+    let bar = bar.clone();
+    let source = glib::timeout_add_local(Duration::from_millis(100), move || {
+        bar.pulse();
+        glib::ControlFlow::Continue
+    });
+    *pulse_source.borrow_mut() = Some(source);
+}
+
+fn stop_progress_pulse(pulse_source: &Rc<RefCell<Option<glib::SourceId>>>) {
+    if let Some(source) = pulse_source.borrow_mut().take() {
+        source.remove();
+    }
+}
+
+// Indicateur REC : clignote de l'entrée à la sortie d'`is_recording`
+fn start_rec_blink(indicator: &Label, blink_source: &Rc<RefCell<Option<glib::SourceId>>>) {
+    stop_rec_blink(indicator, blink_source);
+    indicator.set_opacity(1.0);
+    indicator.set_visible(true);
+    let indicator = indicator.clone();
+    let source = glib::timeout_add_local(Duration::from_millis(500), move || {
+        indicator.set_opacity(if indicator.opacity() > 0.5 { 0.3 } else { 1.0 });
+        glib::ControlFlow::Continue
+    });
+    *blink_source.borrow_mut() = Some(source);
+}
+
+fn stop_rec_blink(indicator: &Label, blink_source: &Rc<RefCell<Option<glib::SourceId>>>) {
+    if let Some(source) = blink_source.borrow_mut().take() {
+        source.remove();
+    }
+    indicator.set_visible(false);
+}
+
+fn buffer_text(buffer: &TextBuffer) -> String {
+    let (start, end) = buffer.bounds();
+    buffer.text(&start, &end, false).to_string()
+}
+
+// Nombre de mots et de caractères (Unicode, pas d'octets)
+fn count_label_text(text: &str) -> String {
+    let plural = |count: usize, unit: &str| format!("{} {}{}", count, unit, if count == 1 { "" } else { "s" });
+    let words = text.split_whitespace().count();
+    let chars = text.chars().count();
+    format!("{} · {}", plural(words, "word"), plural(chars, "character"))
+}
+
+// Transcription en Markdown : titre horodaté puis citation ligne par ligne
+fn to_markdown(text: &str, date: &chrono::DateTime<chrono::Local>) -> String {
+    let mut markdown = format!("## {}\n\n", date.format("%Y-%m-%d %H:%M"));
+    for line in text.lines() {
+        if line.trim().is_empty() {
+            markdown.push_str(">\n");
+        } else {
+            markdown.push_str("> ");
+            markdown.push_str(line);
+            markdown.push('\n');
+        }
+    }
+    markdown
+}
+
+// Fréquences de capture proposées. `None` = 16kHz si supporté (défaut)
+const CAPTURE_RATES: &[(&str, Option<u32>)] = &[
+    ("Auto (16 kHz)", None),
+    ("44.1 kHz", Some(44100)),
+    ("48 kHz", Some(48000)),
+    ("96 kHz", Some(96000)),
+];
+
+fn selected_capture_rate(dropdown: &DropDown) -> Option<u32> {
+    CAPTURE_RATES
+        .get(dropdown.selected() as usize)
+        .and_then(|(_, rate)| *rate)
+}
+
+// Recrée le recorder dans un thread (changement de périphérique ou de fréquence)
+fn reopen_recorder(
+    event_tx: mpsc::Sender<AudioEvent>,
+    device_name: Option<String>,
+    config: RecorderConfig,
+    sender: async_channel::Sender<AppMsg>,
+) {
+    thread::spawn(move || {
+        let msg = match AudioRecorder::new(event_tx, device_name.as_deref(), config) {
+            Ok(recorder) => AppMsg::DeviceChanged(Arc::new(Mutex::new(recorder))),
+            Err(e) => AppMsg::DeviceError(e.to_string()),
+        };
+        let _ = sender.send_blocking(msg);
+    });
+}
+
+// Le bouton d'enregistrement peut être réactivé : pas de changement de
+// modèle ni de transcription en cours
+fn can_record(state: &Mutex<Phase>) -> bool {
+    state
+        .lock()
+        .unwrap()
+        .ready()
+        .is_some_and(|s| !s.reloading && !s.transcribing)
+}
+
+// Config du recorder si aucun enregistrement n'est en cours
+fn idle_recorder_config(state: &Mutex<Phase>) -> Option<RecorderConfig> {
+    let guard = state.lock().unwrap();
+    let app_state = guard.ready()?;
+    if app_state.is_recording || app_state.dictating {
+        None
+    } else {
+        Some(app_state.recorder.lock().unwrap().config())
+    }
+}
+
+// Entrée 0 du sélecteur = périphérique par défaut
+fn selected_device(dropdown: &DropDown, devices: &[InputDevice]) -> Option<String> {
+    (dropdown.selected() as usize)
+        .checked_sub(1)
+        .and_then(|i| devices.get(i))
+        .map(|device| device.name.clone())
+}
+
+fn autosave_tooltip(dir: Option<&std::path::Path>) -> String {
+    match dir {
+        Some(dir) => format!("Appending every transcription to a dated file in {}", dir.display()),
+        None => "Append every transcription to a dated file in a folder".to_string(),
+    }
+}
+
+// Langue enregistrée dans les réglages, langue par défaut si elle n'est plus proposée
+fn language_index(code: Option<&str>) -> u32 {
+    LANGUAGES
+        .iter()
+        .position(|(_, language)| *language == code)
+        .map_or(DEFAULT_LANGUAGE_INDEX, |index| index as u32)
+}
+
+// Modifie un réglage et l'enregistre aussitôt, erreur signalée dans la barre d'état
+fn update_settings(settings: &RefCell<Settings>, status: &Label, update: impl FnOnce(&mut Settings)) {
+    update(&mut settings.borrow_mut());
+    if let Err(e) = settings.borrow().save() {
+        status.set_text(&format!("Failed to save settings: {}", e));
+    }
+}
+
+// Délai avant l'enregistrement des réglages modifiés par un curseur
+const SETTINGS_SAVE_DELAY_MS: u64 = 500;
+
+// Comme `update_settings`, mais l'écriture attend que la valeur se stabilise :
+// faire glisser un curseur ne réécrit pas le fichier à chaque cran
+fn update_settings_later(
+    settings: &Rc<RefCell<Settings>>,
+    status: &Label,
+    pending: &Rc<RefCell<Option<glib::SourceId>>>,
+    update: impl FnOnce(&mut Settings),
+) {
+    update(&mut settings.borrow_mut());
+    if let Some(source) = pending.borrow_mut().take() {
+        source.remove();
+    }
+    let settings = settings.clone();
+    let status = status.clone();
+    let pending_clone = pending.clone();
+    let source = glib::timeout_add_local_once(Duration::from_millis(SETTINGS_SAVE_DELAY_MS), move || {
+        pending_clone.borrow_mut().take();
+        if let Err(e) = settings.borrow().save() {
+            status.set_text(&format!("Failed to save settings: {}", e));
+        }
+    });
+    *pending.borrow_mut() = Some(source);
+}
+
+// Modifie la config du recorder une fois l'init terminée
+fn update_recorder_config(state: &Mutex<Phase>, update: impl FnOnce(&mut RecorderConfig)) {
+    if let Some(app_state) = state.lock().unwrap().ready() {
+        let mut recorder = app_state.recorder.lock().unwrap();
+        let mut config = recorder.config();
+        update(&mut config);
+        if let Err(e) = recorder.set_config(config) {
+            warn!("Failed to update recorder config: {}", e);
+        }
+    }
+}
+
+fn selected_language(dropdown: &DropDown) -> Option<String> {
+    LANGUAGES
+        .get(dropdown.selected() as usize)
+        .and_then(|(_, code)| code.map(str::to_string))
+}
+
+// Place du dernier résultat ajouté en fin de texte (dictée continue)
+struct ResultRange {
+    // Offset (en caractères) du début, séparateur compris
+    start: i32,
+    // Nombre de segments et de tags ajoutés avec ce résultat
+    segments: usize,
+    tags: usize,
+}
 
 struct AppState {
     recorder: Arc<Mutex<AudioRecorder>>,
     transcriber: TranscriptionManager,
     is_recording: bool,
+    // Mode dictée continue : réarme l'enregistrement après chaque transcription
+    dictating: bool,
+    // Les résultats sont ajoutés au texte existant au lieu de le remplacer
+    append_mode: bool,
+    // Dernier buffer transcrit, pour relancer la transcription ("Retry")
+    last_samples: Option<(u64, Arc<Vec<f32>>)>,
+    // Résultat de `last_samples` en mode ajout : "Retry" le remplace
+    last_result: Option<ResultRange>,
+    // Transcription relancée par "Retry" en cours
+    retrying: bool,
+    // Changement de modèle en cours : pas de nouvel enregistrement
+    reloading: bool,
+    // Transcription en cours (micro, "Retry" ou fichier)
+    transcribing: bool,
+    // Segments horodatés du texte affiché, pour l'export SRT/VTT/JSON
+    segments: Vec<Segment>,
+}
+
+// Phase de l'application : tant que l'init (micro + modèle) n'est pas terminée,
+// aucun handler ne doit supposer que l'AppState existe.
+enum Phase {
+    Loading,
+    Ready(AppState),
+    Failed,
+}
+
+impl Phase {
+    fn ready(&self) -> Option<&AppState> {
+        match self {
+            Phase::Ready(app_state) => Some(app_state),
+            _ => None,
+        }
+    }
+
+    fn ready_mut(&mut self) -> Option<&mut AppState> {
+        match self {
+            Phase::Ready(app_state) => Some(app_state),
+            _ => None,
+        }
+    }
 }
 
 enum AppMsg {
+    Loading(&'static str),
+    // Init qui continue malgré un risque (ex. mémoire insuffisante pour le modèle)
+    InitWarning(String),
     InitSuccess(Arc<Mutex<AudioRecorder>>, TranscriptionManager),
     InitError(String),
-    TranscriptionSuccess(String),
+    TranscriptionProgress(f64),
+    TranscriptionSuccess(Transcription),
     TranscriptionError(String),
-    AudioStopped(Vec<f32>), // Utilisé pour l'arrêt manuel ET automatique
+    AudioStopped(Recording), // Utilisé pour l'arrêt manuel ET automatique
+    RetryTranscription,
     AudioStartError(String),
+    DeviceChanged(Arc<Mutex<AudioRecorder>>),
+    DeviceError(String),
+    ModelReloaded(String),
+    ModelReloadError(String),
 }
 
 pub fn build_ui(app: &Application) {
@@ -28,6 +449,8 @@ pub fn build_ui(app: &Application) {
         .default_height(400)
         .build();
 
+    let settings = Rc::new(RefCell::new(Settings::load()));
+
     let vbox = Box::new(Orientation::Vertical, 10);
     vbox.set_margin_top(10);
     vbox.set_margin_bottom(10);
@@ -38,6 +461,14 @@ pub fn build_ui(app: &Application) {
     let text_view = TextView::with_buffer(&buffer);
     text_view.set_editable(false);
     text_view.set_wrap_mode(gtk4::WrapMode::Word);
+    // Segments horodatés affichés, repérés par leur tag dans le buffer
+    let segment_tags: Rc<RefCell<Vec<(gtk4::TextTag, Segment)>>> = Rc::new(RefCell::new(Vec::new()));
+
+    // Menu contextuel : copie du segment sous le curseur
+    let segment_menu = gtk4::gio::Menu::new();
+    segment_menu.append(Some("Copy Segment Text"), Some("segment.copy-text"));
+    segment_menu.append(Some("Copy Segment Timing"), Some("segment.copy-timing"));
+    text_view.set_extra_menu(Some(&segment_menu));
     
     let scrolled_window = ScrolledWindow::builder()
         .hscrollbar_policy(gtk4::PolicyType::Never)
@@ -47,15 +478,301 @@ pub fn build_ui(app: &Application) {
 
     let record_button = Button::with_label("Initializing...");
     record_button.set_sensitive(false);
+    record_button.set_hexpand(true);
+
+    let retry_button = Button::with_label("Retry");
+    retry_button.set_tooltip_text(Some("Transcribe the last recording again"));
+    retry_button.set_visible(false);
+
+    let clear_button = Button::with_label("Clear");
+    clear_button.set_tooltip_text(Some("Clear the displayed transcript, dictation keeps running (Ctrl+L)"));
+
+    let new_button = Button::with_label("New");
+    new_button.set_tooltip_text(Some("Start fresh: clear the transcript and the saved session"));
+
+    let copy_button = Button::with_label("Copy");
+    copy_button.set_tooltip_text(Some("Copy the transcript to the clipboard"));
+
+    let paste_file_button = Button::with_label("Transcribe Copied File");
+    paste_file_button.set_tooltip_text(Some("Transcribe the WAV file whose path is on the clipboard"));
+
+    let markdown_button = Button::with_label("Copy as Markdown");
+    markdown_button.set_tooltip_text(Some("Copy the transcript as a dated Markdown quote"));
+
+    let export_button = Button::with_label("Export...");
+    export_button.set_tooltip_text(Some("Save the transcript as text, subtitles (SRT, WebVTT) or JSON"));
+
+    let raw_button = Button::with_label("Save Raw");
+    raw_button.set_tooltip_text(Some("Save the last recording as captured, before trimming and resampling"));
+    raw_button.set_visible(settings.borrow().keep_raw_audio);
+
+    let cancel_button = Button::with_label("Cancel");
+    cancel_button.set_tooltip_text(Some("Discard the current recording (Escape)"));
+    cancel_button.set_sensitive(false);
+
+    let labels: Vec<&str> = LANGUAGES.iter().map(|(label, _)| *label).collect();
+    let language_dropdown = DropDown::from_strings(&labels);
+    language_dropdown.set_selected(language_index(settings.borrow().language.as_deref()));
+
+    // Même réglage dans la fenêtre principale, pour changer de langue en pleine dictée
+    let quick_language_dropdown = DropDown::from_strings(&labels);
+    quick_language_dropdown.set_tooltip_text(Some("Language of the next sentence, can be changed while dictating"));
+    // La synchronisation initiale part du sélecteur des préférences (langue enregistrée)
+    language_dropdown
+        .bind_property("selected", &quick_language_dropdown, "selected")
+        .bidirectional()
+        .sync_create()
+        .build();
+
+    let continuous_check = CheckButton::with_label("Continuous");
+    continuous_check.set_tooltip_text(Some("Keep recording after each pause until dictation is stopped"));
+
+    let devices = audio::list_input_devices();
+    let mut device_labels = vec!["Default input".to_string()];
+    device_labels.extend(devices.iter().map(|device| {
+        if device.is_monitor {
+            format!("{} (system audio)", device.name)
+        } else {
+            device.name.clone()
+        }
+    }));
+    let device_labels: Vec<&str> = device_labels.iter().map(String::as_str).collect();
+    let device_dropdown = DropDown::from_strings(&device_labels);
+    device_dropdown.set_tooltip_text(Some("Input device (monitor sources capture system audio)"));
+    device_dropdown.set_sensitive(false);
+    // Périphérique enregistré absent (débranché...) : entrée par défaut
+    let saved_device = settings.borrow().input_device.clone();
+    if let Some(index) = saved_device.and_then(|name| devices.iter().position(|device| device.name == name)) {
+        device_dropdown.set_selected(index as u32 + 1);
+    }
+    let devices = Rc::new(devices);
+
+    let model_dropdown = DropDown::from_strings(MODELS);
+    let model_index = MODELS.iter().position(|m| *m == settings.borrow().model).unwrap_or(0);
+    model_dropdown.set_selected(model_index as u32);
+    model_dropdown.set_tooltip_text(Some("Larger models are more accurate but slower and need more memory"));
+    model_dropdown.set_sensitive(false);
+
+    let rate_labels: Vec<&str> = CAPTURE_RATES.iter().map(|(label, _)| *label).collect();
+    let rate_dropdown = DropDown::from_strings(&rate_labels);
+    rate_dropdown.set_tooltip_text(Some("Capture sample rate, resampled to 16 kHz for Whisper"));
+    if let Some(index) = CAPTURE_RATES.iter().position(|(_, rate)| *rate == settings.borrow().capture_rate) {
+        rate_dropdown.set_selected(index as u32);
+    }
+    rate_dropdown.set_sensitive(false);
+
+    let preferences_button = Button::from_icon_name("emblem-system-symbolic");
+    preferences_button.set_tooltip_text(Some("Preferences"));
+
+    let level_bar = LevelBar::for_interval(0.0, 1.0);
+    level_bar.set_size_request(120, -1);
+    level_bar.set_valign(gtk4::Align::Center);
+    level_bar.set_tooltip_text(Some("Input level"));
+
+    let compact_toggle = ToggleButton::new();
+    compact_toggle.set_icon_name("view-restore-symbolic");
+    compact_toggle.set_tooltip_text(Some("Compact mode: only the record button and the input level"));
+
+    // Boutons masqués en mode compact (leur visibilité propre est conservée)
+    let secondary_controls = Box::new(Orientation::Horizontal, 10);
+    secondary_controls.append(&quick_language_dropdown);
+    secondary_controls.append(&retry_button);
+    secondary_controls.append(&raw_button);
+    secondary_controls.append(&paste_file_button);
+    secondary_controls.append(&copy_button);
+    secondary_controls.append(&markdown_button);
+    secondary_controls.append(&export_button);
+    secondary_controls.append(&clear_button);
+    secondary_controls.append(&new_button);
+    secondary_controls.append(&preferences_button);
+
+    let controls = Box::new(Orientation::Horizontal, 10);
+    controls.append(&continuous_check);
+    controls.append(&record_button);
+    controls.append(&cancel_button);
+    controls.append(&secondary_controls);
+    controls.append(&level_bar);
+    controls.append(&compact_toggle);
+
+    let pre_emphasis_check = CheckButton::with_label("Pre-emphasis");
+    pre_emphasis_check.set_tooltip_text(Some("Boost high frequencies to help with consonants on dull microphones"));
+    pre_emphasis_check.set_active(settings.borrow().pre_emphasis);
+
+    let split_check = CheckButton::with_label("Split on pauses");
+    split_check.set_tooltip_text(Some("Transcribe long recordings piece by piece, cutting at silences"));
+    split_check.set_active(settings.borrow().split_on_silence);
+
+    let cleanup_check = CheckButton::with_label("Clean up text");
+    cleanup_check.set_tooltip_text(Some("Fix spacing, punctuation and sentence capitalization"));
+    cleanup_check.set_active(settings.borrow().clean_up_text);
+
+    let profanity_check = CheckButton::with_label("Mask profanity");
+    profanity_check.set_tooltip_text(Some("Best-effort: replaces common English and French swear words with asterisks"));
+    profanity_check.set_active(settings.borrow().profanity_filter);
+
+    let autosave_check = CheckButton::with_label("Auto-save");
+    autosave_check.set_active(settings.borrow().autosave_dir.is_some());
+    autosave_check.set_tooltip_text(Some(&autosave_tooltip(settings.borrow().autosave_dir.as_deref())));
+
+    let auto_copy_check = CheckButton::with_label("Auto-copy");
+    auto_copy_check.set_tooltip_text(Some("Copy each transcription to the clipboard automatically"));
+    auto_copy_check.set_active(settings.borrow().auto_copy);
+
+    let auto_stop_check = CheckButton::with_label("Auto-stop");
+    auto_stop_check.set_tooltip_text(Some("Stop recording after a pause; turn off to record until stopped manually"));
+    auto_stop_check.set_active(settings.borrow().auto_stop);
+
+    let monitor_check = CheckButton::with_label("Monitor");
+    monitor_check.set_tooltip_text(Some("Play the microphone back while recording (use headphones to avoid feedback)"));
+    monitor_check.set_active(settings.borrow().monitor);
+
+    let min_length_spin = SpinButton::with_range(0.0, 5.0, 0.1);
+    min_length_spin.set_value(settings.borrow().min_duration_ms as f64 / 1000.0);
+    min_length_spin.set_digits(1);
+    min_length_spin.set_tooltip_text(Some("Recordings shorter than this (in seconds) are discarded"));
+
+    let gain_scale = Scale::with_range(Orientation::Horizontal, MIN_GAIN as f64, MAX_GAIN as f64, 0.1);
+    gain_scale.set_value(settings.borrow().gain.clamp(MIN_GAIN, MAX_GAIN) as f64);
+    gain_scale.set_digits(1);
+    gain_scale.set_draw_value(true);
+    gain_scale.set_hexpand(true);
+    gain_scale.set_tooltip_text(Some("Input gain, to boost quiet microphones"));
+
+    gain_scale.set_size_request(160, -1);
+
+    // Préférences : les widgets gardent leurs handlers, seule la fenêtre change
+    let audio_section = preferences_section(
+        "Audio",
+        Some("Changing the device or sample rate reopens the input, which is not possible while recording. The other audio options apply immediately."),
+    );
+    audio_section.append(&preferences_row("Input device", &device_dropdown));
+    audio_section.append(&preferences_row("Sample rate", &rate_dropdown));
+    audio_section.append(&preferences_row("Gain", &gain_scale));
+    audio_section.append(&preferences_row("Minimum length (s)", &min_length_spin));
+    audio_section.append(&auto_stop_check);
+    audio_section.append(&monitor_check);
+    audio_section.append(&pre_emphasis_check);
+
+    let transcription_section = preferences_section(
+        "Transcription",
+        Some("Applies from the next transcription. Use Retry to transcribe the last recording again with the new options. A new model is downloaded if needed and loaded in the background."),
+    );
+    transcription_section.append(&preferences_row("Model", &model_dropdown));
+    transcription_section.append(&preferences_row("Language", &language_dropdown));
+    transcription_section.append(&split_check);
+    transcription_section.append(&cleanup_check);
+    transcription_section.append(&profanity_check);
+
+    let output_section = preferences_section("Output", None);
+    output_section.append(&auto_copy_check);
+    output_section.append(&autosave_check);
+
+    let preferences_box = Box::new(Orientation::Vertical, 18);
+    preferences_box.set_margin_top(12);
+    preferences_box.set_margin_bottom(12);
+    preferences_box.set_margin_start(12);
+    preferences_box.set_margin_end(12);
+    preferences_box.append(&audio_section);
+    preferences_box.append(&transcription_section);
+    preferences_box.append(&output_section);
+
+    let preferences_window = gtk4::Window::builder()
+        .title("Preferences")
+        .transient_for(&window)
+        .hide_on_close(true)
+        .default_width(420)
+        .child(&preferences_box)
+        .build();
+
+    let progress_bar = ProgressBar::new();
+    progress_bar.set_visible(false);
+
+    let status_label = Label::new(None);
+    status_label.set_xalign(0.0);
+    status_label.set_hexpand(true);
+    status_label.set_ellipsize(gtk4::pango::EllipsizeMode::End);
+    status_label.add_css_class("dim-label");
+
+    let count_label = Label::new(Some(&count_label_text("")));
+    count_label.add_css_class("dim-label");
+
+    let rec_indicator = Label::new(None);
+    rec_indicator.set_markup("<span foreground=\"#e01b24\">●</span> <b>REC</b>");
+    rec_indicator.set_visible(false);
+    let rec_blink = Rc::new(RefCell::new(None::<glib::SourceId>));
+
+    let status_row = Box::new(Orientation::Horizontal, 10);
+    status_row.append(&rec_indicator);
+    status_row.append(&status_label);
+    status_row.append(&count_label);
 
     vbox.append(&scrolled_window);
-    vbox.append(&record_button);
+    vbox.append(&controls);
+    vbox.append(&progress_bar);
+
+    let diagnostics_label = Label::new(Some("No recording yet."));
+    diagnostics_label.set_xalign(0.0);
+    diagnostics_label.set_selectable(true);
+    diagnostics_label.add_css_class("dim-label");
+    let diagnostics_expander = gtk4::Expander::new(Some("Signal diagnostics"));
+    diagnostics_expander.set_child(Some(&diagnostics_label));
+    vbox.append(&diagnostics_expander);
+
+    vbox.append(&status_row);
+
+    // Compteur mis à jour à chaque modification du texte (cumulé en dictée continue)
+    let count_clone = count_label.clone();
+    buffer.connect_changed(move |buffer| {
+        count_clone.set_text(&count_label_text(&buffer_text(buffer)));
+    });
 
     window.set_child(Some(&vbox));
+
+    // Mode compact au démarrage : au-dessus des autres fenêtres via layer-shell
+    // quand le compositeur le permet (Wayland). Ne peut être fait qu'avant
+    // l'affichage de la fenêtre et ne peut pas être défait : le premier plan
+    // suit le réglage au prochain lancement, et une fenêtre au premier plan
+    // garde la mise en page compacte jusque-là.
+    let overlay = settings.borrow().minimal_mode && gtk4_layer_shell::is_supported();
+    if overlay {
+        window.init_layer_shell();
+        window.set_layer(Layer::Overlay);
+        window.set_anchor(Edge::Top, true);
+        window.set_anchor(Edge::Right, true);
+        window.set_keyboard_mode(KeyboardMode::OnDemand);
+    }
+    let set_compact = {
+        let window = window.clone();
+        let scrolled_window = scrolled_window.clone();
+        let secondary_controls = secondary_controls.clone();
+        let level_bar = level_bar.clone();
+        let count_label = count_label.clone();
+        let diagnostics_expander = diagnostics_expander.clone();
+        // La ligne d'état reste visible (indicateur REC, messages), sans le compteur
+        move |compact: bool| {
+            scrolled_window.set_visible(!compact);
+            diagnostics_expander.set_visible(!compact);
+            secondary_controls.set_visible(!compact);
+            count_label.set_visible(!compact);
+            level_bar.set_visible(compact);
+            if compact {
+                window.set_default_size(1, 1);
+            } else {
+                window.set_default_size(600, 400);
+            }
+        }
+    };
+    compact_toggle.set_active(settings.borrow().minimal_mode);
+    set_compact(settings.borrow().minimal_mode);
     window.present();
 
     // App State
-    let state = Arc::new(Mutex::new(None::<AppState>));
+    let state = Arc::new(Mutex::new(Phase::Loading));
+    // Fermeture demandée pendant un enregistrement : on attend sa finalisation
+    let closing = Rc::new(Cell::new(false));
+
+    // Restauration de la dernière transcription
+    buffer.set_text(&config::load_session());
     
     // Main Async Channel for UI
     let (sender, receiver) = async_channel::unbounded();
@@ -68,8 +785,8 @@ pub fn build_ui(app: &Application) {
     thread::spawn(move || {
         while let Ok(event) = audio_event_rx.recv() {
             match event {
-                AudioEvent::AutoStopped(samples) => {
-                    let _ = sender_bridge.send_blocking(AppMsg::AudioStopped(samples));
+                AudioEvent::AutoStopped(recording) => {
+                    let _ = sender_bridge.send_blocking(AppMsg::AudioStopped(recording));
                 }
             }
         }
@@ -77,8 +794,19 @@ pub fn build_ui(app: &Application) {
 
     // Init Thread
     let sender_init = sender.clone();
+    let event_tx_init = audio_event_tx.clone();
+    let device_name = selected_device(&device_dropdown, &devices);
+    let recorder_config = RecorderConfig {
+        capture_rate: selected_capture_rate(&rate_dropdown),
+        keep_raw: settings.borrow().keep_raw_audio,
+        ..RecorderConfig::default()
+    };
+    let model = settings.borrow().model.clone();
+    let replacements = settings.borrow().replacements.clone();
+    let profanity_words = settings.borrow().profanity_words.clone();
     thread::spawn(move || {
-        let recorder = match AudioRecorder::new(audio_event_tx) {
+        let _ = sender_init.send_blocking(AppMsg::Loading("Opening audio device..."));
+        let recorder = match AudioRecorder::new(event_tx_init, device_name.as_deref(), recorder_config) {
             Ok(r) => r,
             Err(e) => {
                 let _ = sender_init.send_blocking(AppMsg::InitError(format!("Audio Init Failed: {}", e)));
@@ -86,7 +814,15 @@ pub fn build_ui(app: &Application) {
             }
         };
 
-        let transcriber = TranscriptionManager::new(std::path::Path::new("."));
+        let transcriber = TranscriptionManager::new(&TranscriptionManager::default_model_dir(), &model);
+        transcriber.set_replacements(replacements);
+        if let Some(words) = profanity_words {
+            transcriber.set_profanity_words(words);
+        }
+        let _ = sender_init.send_blocking(AppMsg::Loading("Model loading, please wait..."));
+        if let Some(warning) = transcriber.memory_warning() {
+            let _ = sender_init.send_blocking(AppMsg::InitWarning(warning));
+        }
         if let Err(e) = transcriber.load_model() {
             let _ = sender_init.send_blocking(AppMsg::InitError(format!("Model Load Failed: {}", e)));
             return;
@@ -98,75 +834,424 @@ pub fn build_ui(app: &Application) {
     // UI Event Loop
     let state_clone = state.clone();
     let button_clone = record_button.clone();
+    let cancel_clone = cancel_button.clone();
     let buffer_clone = buffer.clone();
     let sender_clone = sender.clone();
+    let status_clone = status_label.clone();
+    let language_clone = language_dropdown.clone();
+    let pre_emphasis_clone = pre_emphasis_check.clone();
+    let split_clone = split_check.clone();
+    let device_clone = device_dropdown.clone();
+    let rate_clone = rate_dropdown.clone();
+    let retry_clone = retry_button.clone();
+    let cleanup_clone = cleanup_check.clone();
+    let profanity_clone = profanity_check.clone();
+    let gain_clone = gain_scale.clone();
+    let monitor_clone = monitor_check.clone();
+    let auto_stop_clone = auto_stop_check.clone();
+    let min_length_clone = min_length_spin.clone();
+    let model_clone = model_dropdown.clone();
+    let current_model = Rc::new(Cell::new(model_dropdown.selected()));
+    let current_model_clone = current_model.clone();
+    let settings_clone = settings.clone();
+    let progress_clone = progress_bar.clone();
+    let closing_clone = closing.clone();
+    let window_clone = window.clone();
+    let pulse_source = Rc::new(RefCell::new(None::<glib::SourceId>));
+    let rec_indicator_clone = rec_indicator.clone();
+    let rec_blink_clone = rec_blink.clone();
     let clipboard = gtk4::prelude::WidgetExt::display(&window).clipboard();
+    let segment_tags_clone = segment_tags.clone();
+    let diagnostics_clone = diagnostics_label.clone();
 
     glib::MainContext::default().spawn_local(async move {
+        // Dictée continue : relance l'enregistrement pour la phrase suivante
+        let rearm_dictation = |app_state: &mut AppState| {
+            match app_state.recorder.lock().unwrap().start_recording() {
+                Ok(()) => {
+                    app_state.is_recording = true;
+                    start_rec_blink(&rec_indicator_clone, &rec_blink_clone);
+                    app_state.last_samples = None;
+                    retry_clone.set_visible(false);
+                    button_clone.set_label("Stop dictation");
+                    button_clone.set_sensitive(true);
+                    cancel_clone.set_sensitive(true);
+                }
+                Err(e) => {
+                    app_state.dictating = false;
+                    status_clone.set_text(&format!("Start Error: {}", e));
+                }
+            }
+        };
+
+        // Avertissement de l'init (mémoire), repris dans le statut "Ready"
+        let mut init_warning: Option<String> = None;
+
         while let Ok(msg) = receiver.recv().await {
             match msg {
                 AppMsg::InitSuccess(recorder, transcriber) => {
-                    *state_clone.lock().unwrap() = Some(AppState {
+                    transcriber.set_language(selected_language(&language_clone));
+                    transcriber.set_split_on_silence(split_clone.is_active());
+                    transcriber.set_post_processing(cleanup_clone.is_active());
+                    transcriber.set_profanity_filter(profanity_clone.is_active());
+                    {
+                        let mut recorder = recorder.lock().unwrap();
+                        let mut config = recorder.config();
+                        config.pre_emphasis = pre_emphasis_clone.is_active();
+                        config.gain = gain_clone.value() as f32;
+                        config.monitor = monitor_clone.is_active();
+                        config.auto_stop = auto_stop_clone.is_active();
+                        config.min_duration_ms = (min_length_clone.value() * 1000.0) as u32;
+                        if let Err(e) = recorder.set_config(config) {
+                            status_clone.set_text(&format!("Config Error: {}", e));
+                        }
+                    }
+                    let name = recorder.lock().unwrap().device_name().to_string();
+                    device_clone.set_tooltip_text(Some(&format!("Input device in use: {}", name)));
+                    *state_clone.lock().unwrap() = Phase::Ready(AppState {
                         recorder,
                         transcriber,
                         is_recording: false,
+                        dictating: false,
+                        append_mode: false,
+                        last_samples: None,
+                        last_result: None,
+                        retrying: false,
+                        reloading: false,
+                        transcribing: false,
+                        segments: Vec::new(),
                     });
                     button_clone.set_label("Start Recording");
                     button_clone.set_sensitive(true);
+                    device_clone.set_sensitive(true);
+                    model_clone.set_sensitive(true);
+                    rate_clone.set_sensitive(true);
+                    let model = state_clone.lock().unwrap().ready().and_then(|s| s.transcriber.model_info());
+                    let mut ready = format!("Ready. Input: {}", name);
+                    let mut tooltip = Vec::new();
+                    if let Some(info) = model {
+                        ready.push_str(&format!(" · {}", info));
+                        tooltip.push(format!("Model file: {}", info.path.display()));
+                    }
+                    // Le statut est tronqué : l'avertissement passe en premier
+                    if let Some(warning) = init_warning.take() {
+                        ready = format!("Warning: {} · {}", warning, ready);
+                        tooltip.push(format!("Warning: {}", warning));
+                    }
+                    status_clone.set_text(&ready);
+                    if !tooltip.is_empty() {
+                        status_clone.set_tooltip_text(Some(&tooltip.join("\n")));
+                    }
+                }
+                AppMsg::Loading(step) => {
+                    button_clone.set_label("Loading...");
+                    status_clone.set_text(step);
+                }
+                AppMsg::InitWarning(warning) => {
+                    status_clone.set_text(&format!("Warning: {}", warning));
+                    init_warning = Some(warning);
                 }
                 AppMsg::InitError(e) => {
+                    *state_clone.lock().unwrap() = Phase::Failed;
                     button_clone.set_label("Init Failed");
-                    buffer_clone.set_text(&e);
+                    status_clone.set_text(&e);
+                }
+                AppMsg::TranscriptionProgress(fraction) => {
+                    stop_progress_pulse(&pulse_source);
+                    progress_clone.set_fraction(fraction);
                 }
-                AppMsg::TranscriptionSuccess(text) => {
+                AppMsg::TranscriptionSuccess(transcription) => {
+                    stop_progress_pulse(&pulse_source);
+                    progress_clone.set_visible(false);
                     button_clone.set_label("Start Recording");
                     button_clone.set_sensitive(true);
+
+                    match &transcription.language {
+                        Some(code) => status_clone.set_text(&format!("Language: {}", code)),
+                        None => status_clone.set_text("Language: auto-detected (not reported by engine)"),
+                    }
+
+                    let mut guard = state_clone.lock().unwrap();
+                    let append_mode = guard.ready().is_some_and(|s| s.append_mode);
+                    retry_clone.set_visible(guard.ready().is_some_and(|s| s.last_samples.is_some()));
+
+                    // Retry en mode ajout : le résultat précédent (texte, tags et
+                    // segments) est retiré avant d'ajouter le nouveau
+                    if let Some(app_state) = guard.ready_mut() {
+                        app_state.transcribing = false;
+                        let retrying = std::mem::take(&mut app_state.retrying);
+                        if let (true, true, Some(range)) = (retrying, append_mode, app_state.last_result.take()) {
+                            let start = range.start.min(buffer_clone.char_count());
+                            buffer_clone.delete(&mut buffer_clone.iter_at_offset(start), &mut buffer_clone.end_iter());
+                            remove_last_segment_tags(&buffer_clone, &segment_tags_clone, range.tags);
+                            let kept = app_state.segments.len().saturating_sub(range.segments);
+                            app_state.segments.truncate(kept);
+                        }
+                    }
+
+                    // Les horodatages continuent après ceux déjà affichés
+                    let segments: Vec<Segment> = if append_mode {
+                        let offset = guard.ready().and_then(|s| s.segments.last()).map_or(0.0, |s| s.end);
+                        transcription.segments.into_iter().map(|s| Segment {
+                            start: s.start + offset,
+                            end: s.end + offset,
+                            ..s
+                        }).collect()
+                    } else {
+                        transcription.segments
+                    };
+                    if let Some(app_state) = guard.ready_mut() {
+                        if append_mode {
+                            app_state.segments.extend(segments.iter().cloned());
+                        } else {
+                            app_state.segments = segments.clone();
+                        }
+                    }
                     
-                    let trimmed = text.trim();
-                    if !trimmed.is_empty() {
+                    let auto_copy = settings_clone.borrow().auto_copy;
+                    let trimmed = transcription.text.trim();
+                    if append_mode {
+                        // Dictée continue : on ajoute le résultat à la suite
+                        let mut last_result = None;
+                        if !trimmed.is_empty() {
+                            let mut end = buffer_clone.end_iter();
+                            let start = end.offset();
+                            let tag_count = segment_tags_clone.borrow().len();
+                            if buffer_clone.char_count() > 0 {
+                                buffer_clone.insert(&mut end, " ");
+                            }
+                            let offset = end.offset();
+                            buffer_clone.insert(&mut end, trimmed);
+                            tag_segments(&buffer_clone, &segment_tags_clone, offset, trimmed, &segments);
+                            last_result = Some(ResultRange {
+                                start,
+                                segments: segments.len(),
+                                tags: segment_tags_clone.borrow().len() - tag_count,
+                            });
+                            if auto_copy {
+                                clipboard.set_text(&buffer_text(&buffer_clone));
+                            }
+                        }
+                        if let Some(app_state) = guard.ready_mut() {
+                            app_state.last_result = last_result;
+                        }
+                    } else if !trimmed.is_empty() {
+                        clear_segment_tags(&buffer_clone, &segment_tags_clone);
                         buffer_clone.set_text(trimmed);
-                        clipboard.set_text(trimmed);
+                        tag_segments(&buffer_clone, &segment_tags_clone, 0, trimmed, &segments);
+                        if auto_copy {
+                            clipboard.set_text(trimmed);
+                        }
                     } else {
                         // Ignore empty transcriptions (often noise)
-                        buffer_clone.set_text("... (no speech detected)");
+                        clear_segment_tags(&buffer_clone, &segment_tags_clone);
+                        buffer_clone.set_text("");
+                        status_clone.set_text("No speech detected.");
+                    }
+
+                    // Sauvegarde de la session pour la retrouver au prochain lancement
+                    if !trimmed.is_empty() {
+                        if let Err(e) = config::save_session(&buffer_text(&buffer_clone)) {
+                            warn!("Failed to save session: {}", e);
+                        }
+                    }
+
+                    // Journal : erreur non bloquante, signalée dans la barre d'état
+                    if let (false, Some(dir)) = (trimmed.is_empty(), settings_clone.borrow().autosave_dir.as_deref()) {
+                        if let Err(e) = config::append_autosave(dir, trimmed) {
+                            status_clone.set_text(&format!("Warning: auto-save failed: {}", e));
+                        }
+                    }
+
+                    // Réarmement automatique en mode continu
+                    if let Some(app_state) = guard.ready_mut() {
+                        if app_state.dictating && !app_state.is_recording {
+                            rearm_dictation(app_state);
+                        }
+                    }
+
+                    if closing_clone.get() {
+                        window_clone.close();
                     }
                 }
                 AppMsg::TranscriptionError(e) => {
+                    stop_progress_pulse(&pulse_source);
+                    progress_clone.set_visible(false);
+                    // Une erreur interrompt la dictée continue
+                    if let Some(app_state) = state_clone.lock().unwrap().ready_mut() {
+                        app_state.dictating = false;
+                        app_state.retrying = false;
+                        app_state.transcribing = false;
+                        retry_clone.set_visible(app_state.last_samples.is_some());
+                    }
                     button_clone.set_label("Start Recording");
                     button_clone.set_sensitive(true);
-                    buffer_clone.set_text(&format!("Error: {}", e));
+                    // Le texte déjà transcrit (ou dicté) est conservé
+                    status_clone.set_text(&format!("Error: {}", e));
+
+                    if closing_clone.get() {
+                        window_clone.close();
+                    }
                 }
-                AppMsg::AudioStopped(samples) => {
+                AppMsg::AudioStopped(recording) => {
                     // Mise à jour de l'état interne (important pour le bouton)
                     let mut guard = state_clone.lock().unwrap();
-                    if let Some(app_state) = guard.as_mut() {
+                    if let Some(app_state) = guard.ready_mut() {
+                        // Enregistrement annulé entre-temps : on ignore l'événement
+                        if !app_state.is_recording {
+                            continue;
+                        }
                         app_state.is_recording = false;
                     }
+                    stop_rec_blink(&rec_indicator_clone, &rec_blink_clone);
+                    cancel_clone.set_sensitive(false);
+
+                    // Fermeture en cours : seule une transcription retarde la fermeture
+                    if closing_clone.get() {
+                        let close_action = settings_clone.borrow().close_action;
+                        let has_audio = matches!(recording, Recording::Samples { .. });
+                        if !has_audio || close_action == CloseAction::SaveRaw {
+                            if let Recording::Samples { samples, .. } = &recording {
+                                let saved = config::recording_path()
+                                    .and_then(|path| audio::write_wav(&path, samples, audio::WHISPER_SAMPLE_RATE).map(|()| path));
+                                match saved {
+                                    Ok(path) => info!("Recording saved to {:?}", path),
+                                    Err(e) => warn!("Failed to save recording on close: {}", e),
+                                }
+                            }
+                            window_clone.close();
+                            continue;
+                        }
+                    }
                     
+                    let (id, samples) = match recording {
+                        Recording::Samples { id, samples, stats } => {
+                            diagnostics_clone.set_text(&stats_text(&stats));
+                            (id, samples)
+                        }
+                        Recording::TooShort => {
+                            button_clone.set_label("Start Recording");
+                            button_clone.set_sensitive(true);
+                            if let Some(app_state) = guard.ready_mut() {
+                                if !app_state.append_mode {
+                                    buffer_clone.set_text("");
+                                }
+                                // Un bruit bref ne doit pas interrompre la dictée
+                                if app_state.dictating {
+                                    rearm_dictation(app_state);
+                                }
+                            }
+                            status_clone.set_text("Recording too short, discarded.");
+                            continue;
+                        }
+                        Recording::Silent => {
+                            button_clone.set_label("Start Recording");
+                            button_clone.set_sensitive(true);
+                            // Micro probablement coupé : relancer la dictée ne servirait à rien
+                            if let Some(app_state) = guard.ready_mut() {
+                                app_state.dictating = false;
+                            }
+                            status_clone.set_text("No audio detected — is your mic muted?");
+                            continue;
+                        }
+                        Recording::NoSpeech => {
+                            button_clone.set_label("Start Recording");
+                            button_clone.set_sensitive(true);
+                            if let Some(app_state) = guard.ready_mut() {
+                                if !app_state.append_mode {
+                                    buffer_clone.set_text("");
+                                }
+                                // Le micro fonctionne : la dictée continue
+                                if app_state.dictating {
+                                    rearm_dictation(app_state);
+                                }
+                            }
+                            status_clone.set_text("No speech detected, discarded.");
+                            continue;
+                        }
+                    };
+
                     // UI Update
                     button_clone.set_label("Processing...");
                     button_clone.set_sensitive(false);
 
-                    if samples.is_empty() {
-                        button_clone.set_label("Start Recording");
-                        button_clone.set_sensitive(true);
-                        buffer_clone.set_text("No audio recorded.");
-                    } else {
-                        // Start Transcription
-                        if let Some(app_state) = guard.as_ref() {
-                            let transcriber = app_state.transcriber.clone();
-                            let sender_trans = sender_clone.clone();
-                            thread::spawn(move || {
-                                match transcriber.transcribe(&samples) {
-                                    Ok(text) => { let _ = sender_trans.send_blocking(AppMsg::TranscriptionSuccess(text)); }
-                                    Err(e) => { let _ = sender_trans.send_blocking(AppMsg::TranscriptionError(e.to_string())); }
-                                }
-                            });
+                    // Start Transcription (le buffer est gardé pour "Retry")
+                    if let Some(app_state) = guard.ready_mut() {
+                        let samples = Arc::new(samples);
+                        app_state.last_samples = Some((id, samples.clone()));
+                        app_state.last_result = None;
+                        app_state.transcribing = true;
+                        start_progress_pulse(&progress_clone, &pulse_source);
+                        spawn_transcription(app_state.transcriber.clone(), id, samples, sender_clone.clone());
+                    }
+                }
+                AppMsg::RetryTranscription => {
+                    let mut guard = state_clone.lock().unwrap();
+                    if let Some(app_state) = guard.ready_mut() {
+                        let busy = app_state.is_recording || app_state.reloading;
+                        if let (Some((id, samples)), false) = (app_state.last_samples.clone(), busy) {
+                            app_state.retrying = true;
+                            app_state.transcribing = true;
+                            retry_clone.set_visible(false);
+                            button_clone.set_label("Processing...");
+                            button_clone.set_sensitive(false);
+                            start_progress_pulse(&progress_clone, &pulse_source);
+                            spawn_transcription(app_state.transcriber.clone(), id, samples, sender_clone.clone());
                         }
                     }
                 }
+                AppMsg::DeviceChanged(recorder) => {
+                    let name = recorder.lock().unwrap().device_name().to_string();
+                    // L'ancien recorder est libéré ici (arrêt de son thread)
+                    if let Some(app_state) = state_clone.lock().unwrap().ready_mut() {
+                        app_state.recorder = recorder;
+                    }
+                    button_clone.set_sensitive(can_record(&state_clone));
+                    device_clone.set_sensitive(true);
+                    device_clone.set_tooltip_text(Some(&format!("Input device in use: {}", name)));
+                    rate_clone.set_sensitive(true);
+                    status_clone.set_text(&format!("Input device reopened: {}", name));
+                }
+                AppMsg::ModelReloaded(variant) => {
+                    if let Some(app_state) = state_clone.lock().unwrap().ready_mut() {
+                        app_state.reloading = false;
+                    }
+                    settings_clone.borrow_mut().model = variant.clone();
+                    if let Err(e) = settings_clone.borrow().save() {
+                        warn!("Failed to save settings: {}", e);
+                    }
+                    button_clone.set_label("Start Recording");
+                    button_clone.set_sensitive(true);
+                    model_clone.set_sensitive(true);
+                    status_clone.set_text(&format!("Model {} loaded.", variant));
+                }
+                AppMsg::ModelReloadError(e) => {
+                    // L'ancien modèle a été rechargé : on réaffiche le modèle actif
+                    let mut guard = state_clone.lock().unwrap();
+                    if let Some(app_state) = guard.ready_mut() {
+                        app_state.reloading = false;
+                        let active = app_state.transcriber.model_info().map(|info| info.variant);
+                        if let Some(index) = active.and_then(|v| MODELS.iter().position(|m| *m == v)) {
+                            current_model_clone.set(index as u32);
+                            model_clone.set_selected(index as u32);
+                        }
+                    }
+                    button_clone.set_label("Start Recording");
+                    button_clone.set_sensitive(true);
+                    model_clone.set_sensitive(true);
+                    status_clone.set_text(&format!("Model Error: {}", e));
+                }
+                AppMsg::DeviceError(e) => {
+                    button_clone.set_sensitive(can_record(&state_clone));
+                    device_clone.set_sensitive(true);
+                    rate_clone.set_sensitive(true);
+                    status_clone.set_text(&format!("Device Error: {}", e));
+                }
                 AppMsg::AudioStartError(e) => {
-                     buffer_clone.set_text(&format!("Start Error: {}", e));
+                     if let Some(app_state) = state_clone.lock().unwrap().ready_mut() {
+                         app_state.dictating = false;
+                     }
+                     status_clone.set_text(&format!("Start Error: {}", e));
                 }
             }
         }
@@ -177,12 +1262,38 @@ pub fn build_ui(app: &Application) {
     let button_clone = record_button.clone();
     let buffer_clone = buffer.clone();
     let sender_clone = sender.clone();
+    let continuous_clone = continuous_check.clone();
+    let cancel_clone = cancel_button.clone();
+    let retry_clone = retry_button.clone();
+    let status_clone = status_label.clone();
+    let segment_tags_clone = segment_tags.clone();
+    let rec_indicator_clone = rec_indicator.clone();
+    let rec_blink_clone = rec_blink.clone();
 
+    // Le bouton est désactivé pendant l'init, mais un déclenchement
+    // programmatique (raccourci, emit_clicked) doit aussi être sûr.
     record_button.connect_clicked(move |_| {
         let mut guard = state_clone.lock().unwrap();
-        if let Some(app_state) = guard.as_mut() {
+        match *guard {
+            Phase::Loading => {
+                status_clone.set_text("Model loading, please wait...");
+                return;
+            }
+            Phase::Failed => {
+                status_clone.set_text("Initialization failed, recording is unavailable.");
+                return;
+            }
+            Phase::Ready(ref app_state) if app_state.reloading => {
+                status_clone.set_text("Model loading, please wait...");
+                return;
+            }
+            Phase::Ready(_) => {}
+        }
+        if let Some(app_state) = guard.ready_mut() {
             if app_state.is_recording {
-                // STOP (Manuel)
+                // STOP (Manuel) : termine aussi la dictée continue,
+                // la dernière phrase est tout de même transcrite.
+                app_state.dictating = false;
                 let recorder = app_state.recorder.clone();
                 let sender_stop = sender_clone.clone();
                 
@@ -190,7 +1301,7 @@ pub fn build_ui(app: &Application) {
                 thread::spawn(move || {
                      let res = recorder.lock().unwrap().stop_recording();
                      match res {
-                         Ok(samples) => { let _ = sender_stop.send_blocking(AppMsg::AudioStopped(samples)); }
+                         Ok(recording) => { let _ = sender_stop.send_blocking(AppMsg::AudioStopped(recording)); }
                          Err(e) => { let _ = sender_stop.send_blocking(AppMsg::TranscriptionError(format!("Stop Error: {}", e))); }
                      }
                 });
@@ -199,11 +1310,776 @@ pub fn build_ui(app: &Application) {
                 if let Err(e) = app_state.recorder.lock().unwrap().start_recording() {
                     let _ = sender_clone.send_blocking(AppMsg::AudioStartError(e.to_string()));
                 } else {
+                    // Nouvel enregistrement : l'ancien buffer n'est plus proposé en "Retry"
                     app_state.is_recording = true;
+                    start_rec_blink(&rec_indicator_clone, &rec_blink_clone);
+                    app_state.last_samples = None;
+                    retry_clone.set_visible(false);
+                    cancel_clone.set_sensitive(true);
+                    // Le texte est remis à zéro : ses segments (export, copie) aussi
+                    clear_segment_tags(&buffer_clone, &segment_tags_clone);
+                    app_state.segments.clear();
+                    app_state.last_result = None;
+                    if continuous_clone.is_active() {
+                        app_state.dictating = true;
+                        app_state.append_mode = true;
+                        button_clone.set_label("Stop dictation");
+                        buffer_clone.set_text("");
+                    } else {
+                        app_state.dictating = false;
+                        app_state.append_mode = false;
+                        button_clone.set_label("Stop Recording");
+                        buffer_clone.set_text("");
+                        status_clone.set_text("Recording...");
+                    }
+                }
+            }
+        }
+    });
+
+    // Cancel Handler : jette l'enregistrement sans transcription
+    let state_clone = state.clone();
+    let button_clone = record_button.clone();
+    let buffer_clone = buffer.clone();
+    let status_clone = status_label.clone();
+    let rec_indicator_clone = rec_indicator.clone();
+    let rec_blink_clone = rec_blink.clone();
+
+    cancel_button.connect_clicked(move |cancel| {
+        let mut guard = state_clone.lock().unwrap();
+        if let Some(app_state) = guard.ready_mut() {
+            if !app_state.is_recording {
+                return;
+            }
+            if let Err(e) = app_state.recorder.lock().unwrap().cancel_recording() {
+                status_clone.set_text(&format!("Cancel Error: {}", e));
+                return;
+            }
+            app_state.is_recording = false;
+            app_state.dictating = false;
+            stop_rec_blink(&rec_indicator_clone, &rec_blink_clone);
+            if !app_state.append_mode {
+                buffer_clone.set_text("");
+            }
+            button_clone.set_label("Start Recording");
+            button_clone.set_sensitive(true);
+            cancel.set_sensitive(false);
+            status_clone.set_text("Recording discarded.");
+        }
+    });
+
+    // Escape = Cancel, Ctrl+L = Clear
+    let key_controller = gtk4::EventControllerKey::new();
+    let cancel_clone = cancel_button.clone();
+    let clear_clone = clear_button.clone();
+    key_controller.connect_key_pressed(move |_, key, _, modifier| {
+        if key == gtk4::gdk::Key::Escape && cancel_clone.is_sensitive() {
+            cancel_clone.emit_clicked();
+            return gtk4::glib::Propagation::Stop;
+        }
+        if key == gtk4::gdk::Key::l && modifier.contains(gtk4::gdk::ModifierType::CONTROL_MASK) {
+            clear_clone.emit_clicked();
+            return gtk4::glib::Propagation::Stop;
+        }
+        gtk4::glib::Propagation::Proceed
+    });
+    window.add_controller(key_controller);
+
+    // Copy : copie manuelle, utile quand la copie automatique est désactivée
+    let buffer_clone = buffer.clone();
+    let status_clone = status_label.clone();
+    let clipboard = gtk4::prelude::WidgetExt::display(&window).clipboard();
+
+    copy_button.connect_clicked(move |_| {
+        let text = buffer_text(&buffer_clone);
+        if text.trim().is_empty() {
+            status_clone.set_text("Nothing to copy.");
+            return;
+        }
+        clipboard.set_text(text.trim());
+        status_clone.set_text("Transcript copied.");
+    });
+
+    // Copy as Markdown : s'ajoute à la copie automatique en texte brut
+    let buffer_clone = buffer.clone();
+    let status_clone = status_label.clone();
+    let clipboard = gtk4::prelude::WidgetExt::display(&window).clipboard();
+
+    markdown_button.connect_clicked(move |_| {
+        let text = buffer_text(&buffer_clone);
+        if text.trim().is_empty() {
+            status_clone.set_text("Nothing to copy.");
+            return;
+        }
+        clipboard.set_text(&to_markdown(text.trim(), &chrono::Local::now()));
+        status_clone.set_text("Transcript copied as Markdown.");
+    });
+
+    // Device Selector : recrée le recorder sur le périphérique choisi
+    let state_clone = state.clone();
+    let button_clone = record_button.clone();
+    let status_clone = status_label.clone();
+    let sender_clone = sender.clone();
+    let event_tx = audio_event_tx.clone();
+    let devices_clone = devices.clone();
+    let rate_clone = rate_dropdown.clone();
+    let settings_clone = settings.clone();
+    let current_device = Rc::new(Cell::new(device_dropdown.selected()));
+
+    device_dropdown.connect_selected_notify(move |dropdown| {
+        if dropdown.selected() == current_device.get() {
+            return;
+        }
+        if state_clone.lock().unwrap().ready().is_none() {
+            return;
+        }
+        let Some(config) = idle_recorder_config(&state_clone) else {
+            // Pas de changement en cours d'enregistrement : on revient au choix précédent
+            status_clone.set_text("Stop recording before switching input device.");
+            dropdown.set_selected(current_device.get());
+            return;
+        };
+
+        current_device.set(dropdown.selected());
+        dropdown.set_sensitive(false);
+        rate_clone.set_sensitive(false);
+        button_clone.set_sensitive(false);
+
+        let device_name = selected_device(dropdown, &devices_clone);
+        update_settings(&settings_clone, &status_clone, |settings| settings.input_device = device_name.clone());
+        reopen_recorder(event_tx.clone(), device_name, config, sender_clone.clone());
+    });
+
+    // Capture Rate Selector : même principe, le flux doit être rouvert
+    let state_clone = state.clone();
+    let button_clone = record_button.clone();
+    let status_clone = status_label.clone();
+    let sender_clone = sender.clone();
+    let device_clone = device_dropdown.clone();
+    let settings_clone = settings.clone();
+    let current_rate = Rc::new(Cell::new(rate_dropdown.selected()));
+
+    rate_dropdown.connect_selected_notify(move |dropdown| {
+        if dropdown.selected() == current_rate.get() {
+            return;
+        }
+        if state_clone.lock().unwrap().ready().is_none() {
+            return;
+        }
+        let Some(mut config) = idle_recorder_config(&state_clone) else {
+            status_clone.set_text("Stop recording before changing the capture rate.");
+            dropdown.set_selected(current_rate.get());
+            return;
+        };
+
+        current_rate.set(dropdown.selected());
+        config.capture_rate = selected_capture_rate(dropdown);
+        update_settings(&settings_clone, &status_clone, |settings| settings.capture_rate = config.capture_rate);
+        dropdown.set_sensitive(false);
+        device_clone.set_sensitive(false);
+        button_clone.set_sensitive(false);
+
+        let device_name = selected_device(&device_clone, &devices);
+        reopen_recorder(audio_event_tx.clone(), device_name, config, sender_clone.clone());
+    });
+
+    // Retry : relance la transcription du dernier buffer
+    let sender_clone = sender.clone();
+    retry_button.connect_clicked(move |_| {
+        let _ = sender_clone.send_blocking(AppMsg::RetryTranscription);
+    });
+
+    // Clear : vide le texte affiché sans toucher à l'enregistrement en cours.
+    // Un résultat encore en vol s'ajoute au buffer vidé (mode continu) ou le
+    // remplace : l'ancien texte ne peut pas revenir, la session suit le buffer.
+    let state_clone = state.clone();
+    let buffer_clone = buffer.clone();
+    let status_clone = status_label.clone();
+
+    let segment_tags_clone = segment_tags.clone();
+
+    clear_button.connect_clicked(move |_| {
+        clear_segment_tags(&buffer_clone, &segment_tags_clone);
+        buffer_clone.set_text("");
+        if let Some(app_state) = state_clone.lock().unwrap().ready_mut() {
+            app_state.segments.clear();
+            app_state.last_result = None;
+            // Hors dictée, le prochain résultat repart en mode remplacement
+            if !app_state.dictating {
+                app_state.append_mode = false;
+            }
+        }
+        status_clone.set_text("Transcript cleared.");
+    });
+
+    // New : repart d'une transcription vide
+    let state_clone = state.clone();
+    let buffer_clone = buffer.clone();
+    let status_clone = status_label.clone();
+
+    let segment_tags_clone = segment_tags.clone();
+
+    new_button.connect_clicked(move |_| {
+        clear_segment_tags(&buffer_clone, &segment_tags_clone);
+        buffer_clone.set_text("");
+        if let Some(app_state) = state_clone.lock().unwrap().ready_mut() {
+            app_state.segments.clear();
+            app_state.last_result = None;
+        }
+        match config::clear_session() {
+            Ok(()) => status_clone.set_text("Started fresh."),
+            Err(e) => status_clone.set_text(&format!("Failed to clear session: {}", e)),
+        }
+    });
+
+    // Language Selector : pris en compte dès la prochaine transcription, sans
+    // interrompre la dictée ni la phrase en cours de transcription
+    let state_clone = state.clone();
+    let status_clone = status_label.clone();
+    let progress_clone = progress_bar.clone();
+    let settings_clone = settings.clone();
+    language_dropdown.connect_selected_notify(move |dropdown| {
+        update_settings(&settings_clone, &status_clone, |settings| settings.language = selected_language(dropdown));
+        if let Some(app_state) = state_clone.lock().unwrap().ready() {
+            app_state.transcriber.set_language(selected_language(dropdown));
+            let label = LANGUAGES.get(dropdown.selected() as usize).map_or("", |(label, _)| *label);
+            if app_state.dictating || app_state.is_recording || progress_clone.is_visible() {
+                status_clone.set_text(&format!("Language: {} (from the next sentence)", label));
+            } else {
+                status_clone.set_text(&format!("Language: {}", label));
+            }
+        }
+    });
+
+    // Continuous Toggle : le décocher termine la dictée après la phrase en cours
+    let state_clone = state.clone();
+    let button_clone = record_button.clone();
+    continuous_check.connect_toggled(move |check| {
+        if !check.is_active() {
+            if let Some(app_state) = state_clone.lock().unwrap().ready_mut() {
+                if app_state.dictating && app_state.is_recording {
                     button_clone.set_label("Stop Recording");
-                    buffer_clone.set_text("Recording...");
                 }
+                app_state.dictating = false;
             }
         }
     });
-}
\ No newline at end of file
+
+    // Pre-emphasis Toggle
+    let state_clone = state.clone();
+    let settings_clone = settings.clone();
+    let status_clone = status_label.clone();
+    pre_emphasis_check.connect_toggled(move |check| {
+        update_settings(&settings_clone, &status_clone, |settings| settings.pre_emphasis = check.is_active());
+        update_recorder_config(&state_clone, |config| config.pre_emphasis = check.is_active());
+    });
+
+    // Split Toggle
+    let state_clone = state.clone();
+    let settings_clone = settings.clone();
+    let status_clone = status_label.clone();
+    split_check.connect_toggled(move |check| {
+        update_settings(&settings_clone, &status_clone, |settings| settings.split_on_silence = check.is_active());
+        if let Some(app_state) = state_clone.lock().unwrap().ready() {
+            app_state.transcriber.set_split_on_silence(check.is_active());
+        }
+    });
+
+    // Gain Slider
+    let pending_save = Rc::new(RefCell::new(None::<glib::SourceId>));
+    let state_clone = state.clone();
+    let settings_clone = settings.clone();
+    let status_clone = status_label.clone();
+    let pending_clone = pending_save.clone();
+    gain_scale.connect_value_changed(move |scale| {
+        update_settings_later(&settings_clone, &status_clone, &pending_clone, |settings| settings.gain = scale.value() as f32);
+        update_recorder_config(&state_clone, |config| config.gain = scale.value() as f32);
+    });
+
+    // Clean-up Toggle
+    let state_clone = state.clone();
+    let settings_clone = settings.clone();
+    let status_clone = status_label.clone();
+    cleanup_check.connect_toggled(move |check| {
+        update_settings(&settings_clone, &status_clone, |settings| settings.clean_up_text = check.is_active());
+        if let Some(app_state) = state_clone.lock().unwrap().ready() {
+            app_state.transcriber.set_post_processing(check.is_active());
+        }
+    });
+
+    // Profanity Toggle
+    let state_clone = state.clone();
+    let settings_clone = settings.clone();
+    let status_clone = status_label.clone();
+    profanity_check.connect_toggled(move |check| {
+        update_settings(&settings_clone, &status_clone, |settings| settings.profanity_filter = check.is_active());
+        if let Some(app_state) = state_clone.lock().unwrap().ready() {
+            app_state.transcriber.set_profanity_filter(check.is_active());
+        }
+    });
+
+    // Auto-save Toggle : choix du dossier à l'activation
+    let settings_clone = settings.clone();
+    let status_clone = status_label.clone();
+    let window_clone = window.clone();
+
+    autosave_check.connect_toggled(move |check| {
+        if !check.is_active() {
+            settings_clone.borrow_mut().autosave_dir = None;
+            check.set_tooltip_text(Some(&autosave_tooltip(None)));
+            if let Err(e) = settings_clone.borrow().save() {
+                status_clone.set_text(&format!("Failed to save settings: {}", e));
+            }
+            return;
+        }
+        if settings_clone.borrow().autosave_dir.is_some() {
+            return;
+        }
+
+        let dialog = gtk4::FileChooserDialog::new(
+            Some("Auto-save folder"),
+            Some(&window_clone),
+            gtk4::FileChooserAction::SelectFolder,
+            &[("Cancel", gtk4::ResponseType::Cancel), ("Select", gtk4::ResponseType::Accept)],
+        );
+        let settings_dialog = settings_clone.clone();
+        let status_dialog = status_clone.clone();
+        let check_dialog = check.clone();
+        dialog.connect_response(move |dialog, response| {
+            let dir = dialog.file().and_then(|file| file.path());
+            match (response, dir) {
+                (gtk4::ResponseType::Accept, Some(dir)) => {
+                    check_dialog.set_tooltip_text(Some(&autosave_tooltip(Some(&dir))));
+                    settings_dialog.borrow_mut().autosave_dir = Some(dir);
+                    if let Err(e) = settings_dialog.borrow().save() {
+                        status_dialog.set_text(&format!("Failed to save settings: {}", e));
+                    }
+                }
+                // Annulé : l'auto-save reste désactivé
+                _ => check_dialog.set_active(false),
+            }
+            dialog.destroy();
+        });
+        dialog.present();
+    });
+
+    // Monitor Toggle
+    let state_clone = state.clone();
+    let settings_clone = settings.clone();
+    let status_clone = status_label.clone();
+    monitor_check.connect_toggled(move |check| {
+        update_settings(&settings_clone, &status_clone, |settings| settings.monitor = check.is_active());
+        update_recorder_config(&state_clone, |config| config.monitor = check.is_active());
+    });
+
+    // Auto-stop Toggle
+    let state_clone = state.clone();
+    let settings_clone = settings.clone();
+    let status_clone = status_label.clone();
+    auto_stop_check.connect_toggled(move |check| {
+        update_settings(&settings_clone, &status_clone, |settings| settings.auto_stop = check.is_active());
+        update_recorder_config(&state_clone, |config| config.auto_stop = check.is_active());
+    });
+
+    // Min Length
+    let state_clone = state.clone();
+    let settings_clone = settings.clone();
+    let status_clone = status_label.clone();
+    let pending_clone = pending_save.clone();
+    min_length_spin.connect_value_changed(move |spin| {
+        update_settings_later(&settings_clone, &status_clone, &pending_clone, |settings| settings.min_duration_ms = (spin.value() * 1000.0) as u32);
+        update_recorder_config(&state_clone, |config| config.min_duration_ms = (spin.value() * 1000.0) as u32);
+    });
+
+    // Fermeture : un réglage encore en attente est enregistré tout de suite
+    let settings_clone = settings.clone();
+    window.connect_close_request(move |_| {
+        if let Some(source) = pending_save.borrow_mut().take() {
+            source.remove();
+            if let Err(e) = settings_clone.borrow().save() {
+                warn!("Failed to save settings: {}", e);
+            }
+        }
+        gtk4::glib::Propagation::Proceed
+    });
+
+    // Fermeture pendant un enregistrement : le buffer est finalisé avant de quitter
+    let state_clone = state.clone();
+    let button_clone = record_button.clone();
+    let cancel_clone = cancel_button.clone();
+    let status_clone = status_label.clone();
+    let sender_clone = sender.clone();
+
+    window.connect_close_request(move |_| {
+        if closing.get() {
+            return gtk4::glib::Propagation::Proceed;
+        }
+        let mut guard = state_clone.lock().unwrap();
+        let Some(app_state) = guard.ready_mut().filter(|s| s.is_recording) else {
+            return gtk4::glib::Propagation::Proceed;
+        };
+
+        closing.set(true);
+        app_state.dictating = false;
+        let recorder = app_state.recorder.clone();
+        let sender_stop = sender_clone.clone();
+        thread::spawn(move || {
+            let msg = match recorder.lock().unwrap().stop_recording() {
+                Ok(recording) => AppMsg::AudioStopped(recording),
+                Err(e) => AppMsg::TranscriptionError(format!("Stop Error: {}", e)),
+            };
+            let _ = sender_stop.send_blocking(msg);
+        });
+
+        button_clone.set_sensitive(false);
+        cancel_clone.set_sensitive(false);
+        status_clone.set_text("Finishing recording before closing...");
+        gtk4::glib::Propagation::Stop
+    });
+
+    // Save Raw : buffer tel que capturé, pour comparer avec ce qui part à Whisper
+    let state_clone = state.clone();
+    let status_clone = status_label.clone();
+    raw_button.connect_clicked(move |_| {
+        let raw = state_clone.lock().unwrap().ready().and_then(|s| s.recorder.lock().unwrap().last_raw_recording());
+        let Some((samples, sample_rate)) = raw else {
+            status_clone.set_text("No raw recording available.");
+            return;
+        };
+        let saved = config::recording_path().and_then(|path| audio::write_wav(&path, &samples, sample_rate).map(|()| path));
+        match saved {
+            Ok(path) => status_clone.set_text(&format!("Raw recording saved to {}", path.display())),
+            Err(e) => status_clone.set_text(&format!("Save Error: {}", e)),
+        }
+    });
+
+    // Auto-copy : préférence persistée dans les réglages
+    let settings_clone = settings.clone();
+    let status_clone = status_label.clone();
+    auto_copy_check.connect_toggled(move |check| {
+        settings_clone.borrow_mut().auto_copy = check.is_active();
+        if let Err(e) = settings_clone.borrow().save() {
+            status_clone.set_text(&format!("Failed to save settings: {}", e));
+        }
+    });
+
+    // Transcribe Copied File : lit un chemin dans le presse-papiers.
+    // Un contenu invalide donne juste un message dans la barre d'état.
+    let state_clone = state.clone();
+    let button_clone = record_button.clone();
+    let status_clone = status_label.clone();
+    let sender_clone = sender.clone();
+    let progress_clone = progress_bar.clone();
+    let clipboard = gtk4::prelude::WidgetExt::display(&window).clipboard();
+
+    paste_file_button.connect_clicked(move |_| {
+        let transcriber = match state_clone.lock().unwrap().ready() {
+            Some(app_state) if app_state.reloading => {
+                status_clone.set_text("Model loading, please wait...");
+                return;
+            }
+            Some(app_state) if !app_state.is_recording => app_state.transcriber.clone(),
+            Some(_) => {
+                status_clone.set_text("Stop recording before transcribing a file.");
+                return;
+            }
+            None => {
+                status_clone.set_text("Model loading, please wait...");
+                return;
+            }
+        };
+
+        let clipboard = clipboard.clone();
+        let button_clone = button_clone.clone();
+        let status_clone = status_clone.clone();
+        let sender_clone = sender_clone.clone();
+        let progress_clone = progress_clone.clone();
+        let state_clone = state_clone.clone();
+        glib::MainContext::default().spawn_local(async move {
+            let text = clipboard.read_text_future().await.ok().flatten();
+            let Some(path) = text.as_deref().and_then(clipboard_audio_path) else {
+                status_clone.set_text("The clipboard does not contain a path to a WAV file.");
+                return;
+            };
+
+            // Résultat affiché comme une transcription normale (remplace le texte)
+            // "Retry" ne concerne que les enregistrements du micro
+            if let Some(app_state) = state_clone.lock().unwrap().ready_mut() {
+                app_state.append_mode = false;
+                app_state.last_samples = None;
+                app_state.transcribing = true;
+            }
+            button_clone.set_label("Processing...");
+            button_clone.set_sensitive(false);
+            status_clone.set_text(&format!("Transcribing {}...", path.display()));
+            progress_clone.set_fraction(0.0);
+            progress_clone.set_visible(true);
+            progress_clone.pulse();
+            thread::spawn(move || {
+                let msg = match transcriber.transcribe_file(&path) {
+                    Ok(transcription) => AppMsg::TranscriptionSuccess(transcription),
+                    Err(e) => AppMsg::TranscriptionError(e.to_string()),
+                };
+                let _ = sender_clone.send_blocking(msg);
+            });
+        });
+    });
+
+    // Preferences : fenêtre masquée à la fermeture, réglages conservés
+    preferences_button.connect_clicked(move |_| {
+        preferences_window.present();
+    });
+
+    // Model Selector : rechargement en arrière-plan, sans redémarrer.
+    // Bloqué pendant un enregistrement ; une transcription en cours termine
+    // avec l'ancien moteur avant l'échange.
+    let state_clone = state.clone();
+    let button_clone = record_button.clone();
+    let status_clone = status_label.clone();
+    let sender_clone = sender.clone();
+
+    model_dropdown.connect_selected_notify(move |dropdown| {
+        if dropdown.selected() == current_model.get() {
+            return;
+        }
+        let mut guard = state_clone.lock().unwrap();
+        let Some(app_state) = guard.ready_mut() else {
+            dropdown.set_selected(current_model.get());
+            return;
+        };
+        if app_state.is_recording || app_state.dictating || app_state.reloading {
+            status_clone.set_text("Stop recording before switching model.");
+            dropdown.set_selected(current_model.get());
+            return;
+        }
+        let Some(variant) = MODELS.get(dropdown.selected() as usize).copied() else {
+            return;
+        };
+
+        current_model.set(dropdown.selected());
+        app_state.reloading = true;
+        dropdown.set_sensitive(false);
+        button_clone.set_label("Loading...");
+        button_clone.set_sensitive(false);
+        status_clone.set_text(&format!("Loading model {} (downloading if needed)...", variant));
+
+        let transcriber = app_state.transcriber.clone();
+        let sender_reload = sender_clone.clone();
+        thread::spawn(move || {
+            let msg = match transcriber.switch_model(variant) {
+                Ok(()) => AppMsg::ModelReloaded(variant.to_string()),
+                Err(e) => AppMsg::ModelReloadError(e.to_string()),
+            };
+            let _ = sender_reload.send_blocking(msg);
+        });
+    });
+
+    // Compact Toggle : préférence retenue dans les réglages
+    let settings_clone = settings.clone();
+    let status_clone = status_label.clone();
+    compact_toggle.connect_toggled(move |toggle| {
+        let compact = toggle.is_active();
+        update_settings(&settings_clone, &status_clone, |settings| settings.minimal_mode = compact);
+        if overlay {
+            // Une surface layer-shell ne redevient pas une fenêtre normale
+            if !compact {
+                status_clone.set_text("Full layout at the next start; the window stays compact and on top until then.");
+            }
+            return;
+        }
+        set_compact(compact);
+        if compact && gtk4_layer_shell::is_supported() {
+            status_clone.set_text("The compact window will stay on top from the next start.");
+        }
+    });
+
+    // Vumètre : try_lock pour ne jamais bloquer l'UI derrière un arrêt en cours
+    let state_clone = state.clone();
+    glib::timeout_add_local(Duration::from_millis(100), move || {
+        if level_bar.is_visible() {
+            let level = state_clone
+                .try_lock()
+                .ok()
+                .and_then(|guard| {
+                    let app_state = guard.ready().filter(|s| s.is_recording)?;
+                    let level = app_state.recorder.try_lock().ok()?.level();
+                    Some(level)
+                })
+                .unwrap_or(0.0);
+            level_bar.set_value(level as f64);
+        }
+        glib::ControlFlow::Continue
+    });
+
+    // Export... : un seul dialogue, le format choisi fixe l'extension. Sans
+    // horodatages (session restaurée...), seul le texte brut peut être exporté.
+    let state_clone = state.clone();
+    let buffer_clone = buffer.clone();
+    let status_clone = status_label.clone();
+    let window_clone = window.clone();
+    export_button.connect_clicked(move |_| {
+        let text = buffer_text(&buffer_clone);
+        if text.trim().is_empty() {
+            status_clone.set_text("Nothing to export.");
+            return;
+        }
+        let (segments, can_retry) = state_clone
+            .lock()
+            .unwrap()
+            .ready()
+            .map(|s| (s.segments.clone(), s.last_samples.is_some()))
+            .unwrap_or_default();
+
+        let dialog = gtk4::FileChooserDialog::new(
+            Some("Export Transcript"),
+            Some(&window_clone),
+            gtk4::FileChooserAction::Save,
+            &[("Cancel", gtk4::ResponseType::Cancel), ("Export", gtk4::ResponseType::Accept)],
+        );
+        let labels: Vec<&str> = ExportFormat::ALL.iter().map(|format| format.label()).collect();
+        let format_dropdown = DropDown::from_strings(&labels);
+        let note = Label::new(Some(if can_retry {
+            "This transcript has no timestamps. Use Retry to transcribe it again, then export."
+        } else {
+            "This transcript has no timestamps, only plain text can be exported."
+        }));
+        note.add_css_class("dim-label");
+        note.set_wrap(true);
+        note.set_xalign(0.0);
+        let format_box = Box::new(Orientation::Vertical, 6);
+        format_box.set_margin_start(12);
+        format_box.set_margin_end(12);
+        format_box.set_margin_bottom(12);
+        format_box.append(&preferences_row("Format", &format_dropdown));
+        format_box.append(&note);
+        dialog.content_area().append(&format_box);
+
+        let name = format!("transcript_{}", chrono::Local::now().format("%Y-%m-%d_%H%M"));
+        dialog.set_current_name(&format!("{}.{}", name, ExportFormat::Text.extension()));
+        let update_format = {
+            let dialog = dialog.clone();
+            let note = note.clone();
+            let has_segments = !segments.is_empty();
+            move |dropdown: &DropDown| {
+                let format = ExportFormat::ALL[dropdown.selected() as usize];
+                // Garde le nom saisi, seule l'extension suit le format
+                let current = dialog.current_name().map(|n| n.to_string()).unwrap_or_default();
+                let stem = std::path::Path::new(&current)
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().into_owned())
+                    .filter(|stem| !stem.is_empty())
+                    .unwrap_or_else(|| name.clone());
+                dialog.set_current_name(&format!("{}.{}", stem, format.extension()));
+                let missing = format.needs_segments() && !has_segments;
+                dialog.set_response_sensitive(gtk4::ResponseType::Accept, !missing);
+                note.set_visible(missing);
+            }
+        };
+        update_format(&format_dropdown);
+        format_dropdown.connect_selected_notify(update_format);
+
+        let status_dialog = status_clone.clone();
+        dialog.connect_response(move |dialog, response| {
+            if let (gtk4::ResponseType::Accept, Some(mut path)) = (response, dialog.file().and_then(|file| file.path())) {
+                let format = ExportFormat::ALL[format_dropdown.selected() as usize];
+                if path.extension().is_none() {
+                    path.set_extension(format.extension());
+                }
+                let written = export_transcript(format, &text, &segments)
+                    .and_then(|content| std::fs::write(&path, content).map_err(Into::into));
+                match written {
+                    Ok(()) => status_dialog.set_text(&format!("Exported to {}", path.display())),
+                    Err(e) => status_dialog.set_text(&format!("Export failed: {}", e)),
+                }
+            }
+            dialog.destroy();
+        });
+        dialog.present();
+    });
+
+    // Copie du segment sous le curseur (menu contextuel du texte)
+    let segment_actions = gtk4::gio::SimpleActionGroup::new();
+    for (name, copy_timing) in [("copy-text", false), ("copy-timing", true)] {
+        let action = gtk4::gio::SimpleAction::new(name, None);
+        let buffer_clone = buffer.clone();
+        let segment_tags_clone = segment_tags.clone();
+        let status_clone = status_label.clone();
+        let clipboard = gtk4::prelude::WidgetExt::display(&window).clipboard();
+        action.connect_activate(move |_, _| {
+            let cursor = buffer_clone.iter_at_mark(&buffer_clone.get_insert());
+            let tags = segment_tags_clone.borrow();
+            let segment = cursor.tags().iter().find_map(|tag| {
+                tags.iter().find(|(segment_tag, _)| segment_tag == tag).map(|(_, segment)| segment)
+            });
+            match segment {
+                Some(segment) if copy_timing => {
+                    clipboard.set_text(&segment.timing());
+                    status_clone.set_text(&format!("Copied timing {}", segment.timing()));
+                }
+                Some(segment) => {
+                    clipboard.set_text(&segment.text);
+                    status_clone.set_text("Segment text copied.");
+                }
+                None => status_clone.set_text("No timed segment at the cursor."),
+            }
+        });
+        segment_actions.add_action(&action);
+    }
+    text_view.insert_action_group("segment", Some(&segment_actions));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(text: &str) -> Segment {
+        Segment { start: 0.0, end: 0.0, text: text.to_string() }
+    }
+
+    // Texte couvert par chaque plage trouvée
+    fn covered(text: &str, segments: &[Segment]) -> Vec<(usize, String)> {
+        let chars: Vec<char> = text.chars().collect();
+        segment_ranges(text, segments)
+            .into_iter()
+            .map(|(index, start, end)| (index, chars[start as usize..end as usize].iter().collect()))
+            .collect()
+    }
+
+    #[test]
+    fn segment_ranges_survive_clean_up() {
+        let text = "Bonjour, ça va\u{a0}? Très bien.";
+        let segments = [segment(" bonjour ,  ça va ?"), segment("très bien .")];
+        assert_eq!(
+            covered(text, &segments),
+            vec![(0, "Bonjour, ça va\u{a0}?".to_string()), (1, "Très bien.".to_string())]
+        );
+    }
+
+    #[test]
+    fn segment_ranges_follow_replacements() {
+        let text = "Open nSpeech now.";
+        let segments = [segment("Open nSpeech"), segment("now.")];
+        assert_eq!(covered(text, &segments), vec![(0, "Open nSpeech".to_string()), (1, "now.".to_string())]);
+    }
+
+    #[test]
+    fn segment_ranges_skip_unmatched_segments() {
+        let text = "First part. Third part.";
+        let segments = [segment("first part."), segment("missing words"), segment("third part.")];
+        assert_eq!(
+            covered(text, &segments),
+            vec![(0, "First part.".to_string()), (2, "Third part.".to_string())]
+        );
+    }
+
+    #[test]
+    fn segment_ranges_handle_lowercase_expansion() {
+        // 'İ' devient deux caractères en minuscules : les plages restent en caractères
+        let text = "İstanbul. Ankara.";
+        let segments = [segment("İSTANBUL."), segment("ankara.")];
+        assert_eq!(
+            covered(text, &segments),
+            vec![(0, "İstanbul.".to_string()), (1, "Ankara.".to_string())]
+        );
+    }
+}
@@ -0,0 +1,3 @@
+// Pipeline audio -> transcription, partagé par l'application et les tests d'intégration
+pub mod audio;
+pub mod transcription;
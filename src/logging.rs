@@ -0,0 +1,100 @@
+use log::{info, warn};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+// Active la copie des logs dans un fichier (en plus de stderr)
+const LOG_FILE_ENV: &str = "NSPEECH_LOG_FILE";
+// Au-delà, le fichier courant devient `nspeech.log.1` (une seule sauvegarde)
+const MAX_LOG_SIZE: u64 = 5 * 1024 * 1024;
+
+fn log_path() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("nspeech").join("nspeech.log"))
+}
+
+// Fichier de log avec rotation sur la taille
+struct RotatingFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+}
+
+impl RotatingFile {
+    fn open(path: PathBuf) -> io::Result<Self> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+        Ok(Self { path, file, size })
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        std::fs::rename(&self.path, self.path.with_extension("log.1"))?;
+        self.file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.size + buf.len() as u64 > MAX_LOG_SIZE {
+            self.rotate()?;
+        }
+        let written = self.file.write(buf)?;
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+// Écrit sur stderr et dans le fichier ; une erreur fichier ne coupe pas stderr
+struct TeeWriter {
+    file: RotatingFile,
+}
+
+impl Write for TeeWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        io::stderr().write_all(buf)?;
+        let _ = self.file.write_all(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let _ = self.file.flush();
+        io::stderr().flush()
+    }
+}
+
+// stderr seul par défaut (RUST_LOG comme avant), fichier en plus si
+// NSPEECH_LOG_FILE est défini (ex. lancement depuis un lanceur de bureau).
+pub fn init() {
+    let enabled = std::env::var(LOG_FILE_ENV).is_ok_and(|value| !value.is_empty() && value != "0");
+    let mut builder = env_logger::Builder::from_default_env();
+
+    if !enabled {
+        builder.init();
+        return;
+    }
+
+    let Some(path) = log_path() else {
+        builder.init();
+        warn!("No data directory, logging to stderr only");
+        return;
+    };
+
+    match RotatingFile::open(path.clone()) {
+        Ok(file) => {
+            builder.target(env_logger::Target::Pipe(Box::new(TeeWriter { file }))).init();
+            info!("Logging to {:?}", path);
+        }
+        Err(e) => {
+            builder.init();
+            warn!("Cannot open log file {:?}: {}, logging to stderr only", path, e);
+        }
+    }
+}
@@ -1,13 +1,28 @@
 mod gui;
-mod audio;
-mod transcription;
+mod batch;
+mod config;
+mod logging;
 
 use gtk4::prelude::*;
 use gtk4::Application;
 
 fn main() {
-    // Initialisation du logger
-    env_logger::init();
+    // Initialisation du logger (stderr, + fichier si NSPEECH_LOG_FILE est défini)
+    logging::init();
+
+    // Mode lots (--batch DIR) : pas d'interface
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(options) = batch::BatchOptions::from_args(&args) {
+        let code = match options.and_then(batch::run) {
+            Ok(0) => 0,
+            Ok(_) => 1,
+            Err(e) => {
+                eprintln!("nspeech: {}", e);
+                2
+            }
+        };
+        std::process::exit(code);
+    }
 
     let app = Application::builder()
         .application_id("com.github.nspeech")
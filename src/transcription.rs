@@ -1,75 +1,1013 @@
+use crate::audio;
 use anyhow::{anyhow, Result};
-use log::info;
+use futures::StreamExt;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
 use transcribe_rs::engines::whisper::{WhisperEngine, WhisperInferenceParams};
 use transcribe_rs::TranscriptionEngine;
 
+// Langue par défaut (code Whisper), aussi celle des réglages neufs
+pub const DEFAULT_LANGUAGE: &str = "fr";
+const MODEL_BASE_URL: &str = "https://huggingface.co/ggerganov/whisper.cpp/resolve/main";
+// Modèles proposés (fichiers `ggml-<variante>.bin` du dépôt whisper.cpp)
+pub const MODELS: &[&str] = &["tiny-q5_1", "base-q5_1", "small-q5_1", "medium-q5_0", "large-v3-q5_0"];
+pub const DEFAULT_MODEL: &str = "base-q5_1";
+// Dossier du modèle imposé (paquets de distribution, disque dédié...)
+const MODEL_DIR_ENV: &str = "NSPEECH_MODEL_DIR";
+// Mémoire approximative nécessaire par taille de modèle (chiffres whisper.cpp)
+const MODEL_MEMORY_MB: &[(&str, u64)] = &[
+    ("tiny", 273),
+    ("base", 388),
+    ("small", 852),
+    ("medium", 2100),
+    ("large", 3900),
+];
+const DOWNLOAD_ATTEMPTS: u32 = 3;
+// Silence minimal pour découper l'audio en morceaux
+const SPLIT_MIN_SILENCE_MS: u32 = 500;
+// Espace insécable, utilisée devant la ponctuation double en français
+const NBSP: char = '\u{a0}';
+
+// Listes par défaut du filtre de grossièretés (anglais et français)
+const DEFAULT_PROFANITY_EN: &[&str] = &[
+    "fuck", "fucking", "fucked", "motherfucker", "shit", "bullshit", "bitch",
+    "bastard", "asshole", "dick", "cunt", "damn",
+];
+const DEFAULT_PROFANITY_FR: &[&str] = &[
+    "merde", "putain", "pute", "connard", "connasse", "con", "conne", "salope",
+    "salaud", "enculé", "enfoiré", "bordel", "foutre", "chier",
+];
+
+// Résultat d'une transcription
+pub struct Transcription {
+    pub text: String,
+    // Langue utilisée pour le décodage. `None` si la détection automatique
+    // était active et que le moteur n'a pas rapporté la langue détectée.
+    pub language: Option<String>,
+    // Segments horodatés (secondes depuis le début de l'audio), avec les mêmes
+    // traitements que `text`. Vide si le moteur n'en a pas fourni.
+    pub segments: Vec<Segment>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Segment {
+    pub start: f32,
+    pub end: f32,
+    pub text: String,
+}
+
+impl Segment {
+    // "00:00:01,200 --> 00:00:03,400", comme dans un fichier SRT
+    pub fn timing(&self) -> String {
+        format!("{} --> {}", format_timestamp(self.start, ','), format_timestamp(self.end, ','))
+    }
+}
+
+// Formats proposés par "Export..."
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    Text,
+    Srt,
+    Vtt,
+    Json,
+}
+
+impl ExportFormat {
+    pub const ALL: [ExportFormat; 4] = [ExportFormat::Text, ExportFormat::Srt, ExportFormat::Vtt, ExportFormat::Json];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ExportFormat::Text => "Plain text (.txt)",
+            ExportFormat::Srt => "SubRip subtitles (.srt)",
+            ExportFormat::Vtt => "WebVTT subtitles (.vtt)",
+            ExportFormat::Json => "JSON with segments (.json)",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Text => "txt",
+            ExportFormat::Srt => "srt",
+            ExportFormat::Vtt => "vtt",
+            ExportFormat::Json => "json",
+        }
+    }
+
+    // Tous les formats sauf le texte brut ont besoin des horodatages
+    pub fn needs_segments(self) -> bool {
+        self != ExportFormat::Text
+    }
+}
+
+// Informations sur le modèle chargé, pour l'affichage
+#[derive(Debug, Clone)]
+pub struct ModelInfo {
+    // Variante tirée du nom de fichier, ex. "base-q5_1"
+    pub variant: String,
+    pub path: PathBuf,
+    pub language: Option<String>,
+}
+
+impl std::fmt::Display for ModelInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let language = self.language.as_deref().map_or("auto".to_string(), str::to_uppercase);
+        write!(f, "{} loaded, {}", self.variant, language)
+    }
+}
+
+// Entrée du dictionnaire personnel, ex. "en speech" -> "nSpeech"
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Replacement {
+    pub from: String,
+    pub to: String,
+    #[serde(default)]
+    pub case_insensitive: bool,
+}
+
+// Réglages partagés entre toutes les copies du manager
+#[derive(Clone, Debug)]
+struct TranscriptionOptions {
+    language: Option<String>,
+    // Découpe l'audio aux silences et transcrit chaque morceau séparément
+    split_on_silence: bool,
+    // Nettoyage du texte (espaces, ponctuation, majuscules)
+    post_process: bool,
+    // Masquage des grossièretés (désactivé par défaut)
+    profanity_filter: bool,
+    profanity_words: Vec<String>,
+    replacements: Vec<Replacement>,
+}
+
+impl Default for TranscriptionOptions {
+    fn default() -> Self {
+        Self {
+            language: Some(DEFAULT_LANGUAGE.to_string()),
+            split_on_silence: false,
+            post_process: true,
+            profanity_filter: false,
+            profanity_words: DEFAULT_PROFANITY_EN
+                .iter()
+                .chain(DEFAULT_PROFANITY_FR)
+                .map(|w| w.to_string())
+                .collect(),
+            replacements: Vec::new(),
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct TranscriptionManager {
     engine: Arc<Mutex<Option<WhisperEngine>>>,
-    model_path: PathBuf,
+    model_dir: PathBuf,
+    // Modèle courant, remplacé par `switch_model`
+    model_path: Arc<Mutex<PathBuf>>,
+    options: Arc<Mutex<TranscriptionOptions>>,
+    // Lu sans prendre le verrou du moteur, tenu pendant toute une transcription
+    loaded: Arc<AtomicBool>,
 }
 
 impl TranscriptionManager {
-    pub fn new(model_dir: &Path) -> Self {
+    // `variant` : une entrée de MODELS, ex. "base-q5_1"
+    pub fn new(model_dir: &Path, variant: &str) -> Self {
         Self {
             engine: Arc::new(Mutex::new(None)),
-            model_path: model_dir.join("ggml-base-q5_1.bin"),
+            model_dir: model_dir.to_path_buf(),
+            model_path: Arc::new(Mutex::new(model_dir.join(model_file_name(variant)))),
+            options: Arc::new(Mutex::new(TranscriptionOptions::default())),
+            loaded: Arc::new(AtomicBool::new(false)),
         }
     }
 
-    pub fn ensure_model_exists(&self) -> Result<()> {
-        if self.model_path.exists() {
-            return Ok(());
+    pub fn is_loaded(&self) -> bool {
+        self.loaded.load(Ordering::Acquire)
+    }
+
+    // `None` tant que le modèle n'est pas chargé
+    pub fn model_info(&self) -> Option<ModelInfo> {
+        if !self.is_loaded() {
+            return None;
         }
+        let path = self.model_path();
+        Some(ModelInfo {
+            variant: model_variant(&path),
+            path,
+            language: self.options.lock().unwrap().language.clone(),
+        })
+    }
 
-        info!("Downloading optimized model to {:?}", self.model_path);
-        let url = "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-base-q5_1.bin";
-        
-        let runtime = tokio::runtime::Runtime::new()?;
-        runtime.block_on(async {
-            let resp = reqwest::get(url).await?.bytes().await?;
-            std::fs::write(&self.model_path, resp)?;
-            Ok::<(), anyhow::Error>(())
-        })?;
-        
-        info!("Model downloaded.");
-        Ok(())
+    // `None` active la détection automatique de la langue
+    pub fn set_language(&self, language: Option<String>) {
+        self.options.lock().unwrap().language = language;
+    }
+
+    // Pour les longs enregistrements : une inférence par phrase plutôt qu'une seule géante
+    pub fn set_split_on_silence(&self, enabled: bool) {
+        self.options.lock().unwrap().split_on_silence = enabled;
+    }
+
+    pub fn set_post_processing(&self, enabled: bool) {
+        self.options.lock().unwrap().post_process = enabled;
+    }
+
+    // Filtre "best effort" : seuls les mots exacts de la liste sont masqués
+    pub fn set_profanity_filter(&self, enabled: bool) {
+        self.options.lock().unwrap().profanity_filter = enabled;
+    }
+
+    // Remplace la liste par défaut (mots comparés sans tenir compte de la casse)
+    pub fn set_profanity_words(&self, words: Vec<String>) {
+        self.options.lock().unwrap().profanity_words = words.into_iter().map(|w| w.to_lowercase()).collect();
+    }
+
+    pub fn set_replacements(&self, replacements: Vec<Replacement>) {
+        self.options.lock().unwrap().replacements = replacements;
+    }
+
+    // NSPEECH_MODEL_DIR, sinon ~/.local/share/nspeech/models, sinon le dossier courant
+    pub fn default_model_dir() -> PathBuf {
+        if let Some(dir) = std::env::var_os(MODEL_DIR_ENV).filter(|dir| !dir.is_empty()) {
+            return PathBuf::from(dir);
+        }
+        dirs::data_dir()
+            .map(|dir| dir.join("nspeech").join("models"))
+            .unwrap_or_else(|| PathBuf::from("."))
+    }
+
+    fn model_path(&self) -> PathBuf {
+        self.model_path.lock().unwrap().clone()
+    }
+
+    // Avertissement si la mémoire disponible (MemAvailable) est inférieure à
+    // ce que demande le modèle. `None` si elle suffit ou n'est pas mesurable.
+    pub fn memory_warning(&self) -> Option<String> {
+        memory_warning(&self.model_path())
+    }
+
+    // Copies supplémentaires du modèle que la mémoire disponible permet encore
+    // de charger (transcription par lots). `None` si ce n'est pas mesurable.
+    pub fn spare_model_slots(&self) -> Option<usize> {
+        let required = model_memory_mb(&self.model_path())?;
+        let available = available_memory_mb()?;
+        Some((available / required) as usize)
     }
 
     pub fn load_model(&self) -> Result<()> {
-        self.ensure_model_exists()?;
+        self.load_model_file(self.model_path())
+    }
+
+    // Change de modèle sans redémarrer (téléchargement si besoin). L'ancien
+    // moteur reste utilisable pendant le téléchargement, puis il est libéré
+    // avant le chargement : les deux modèles ne tiennent pas forcément
+    // ensemble en mémoire. La libération attend la fin d'une éventuelle
+    // transcription en cours (verrou du moteur). En cas d'échec, l'ancien
+    // modèle est rechargé.
+    pub fn switch_model(&self, variant: &str) -> Result<()> {
+        let path = adopt_legacy_model(&self.model_dir.join(model_file_name(variant)));
+        ensure_model_file(&path)?;
+
+        let previous = self.model_path();
+        {
+            let mut guard = self.engine.lock().unwrap();
+            self.loaded.store(false, Ordering::Release);
+            *guard = None;
+        }
+
+        match self.load_model_file(path) {
+            Ok(()) => Ok(()),
+            Err(e) => {
+                if let Err(restore) = self.load_model_file(previous.clone()) {
+                    warn!("Failed to reload previous model {:?}: {}", previous, restore);
+                }
+                Err(e)
+            }
+        }
+    }
+
+    fn load_model_file(&self, path: PathBuf) -> Result<()> {
+        let path = adopt_legacy_model(&path);
+        ensure_model_file(&path)?;
         
+        if let Some(warning) = memory_warning(&path) {
+            warn!("{}", warning);
+        }
+
+        // Un échec d'allocation remonte sous forme d'erreur générique :
+        // on le reformule en conseil quand le message évoque la mémoire.
         let mut engine = WhisperEngine::new();
-        engine.load_model(&self.model_path)
-            .map_err(|e| anyhow!("Failed to load model: {}", e))?;
+        engine.load_model(&path).map_err(|e| {
+            let message = e.to_string();
+            if looks_like_allocation_failure(&message) {
+                anyhow!(
+                    "Not enough memory to load the {} model ({}). Try a smaller model (base or tiny).",
+                    model_variant(&path),
+                    message
+                )
+            } else {
+                anyhow!("Failed to load model: {}", message)
+            }
+        })?;
             
         let mut guard = self.engine.lock().unwrap();
         *guard = Some(engine);
-        
-        info!("Whisper model loaded.");
+        info!("Whisper model {} loaded.", model_variant(&path));
+        *self.model_path.lock().unwrap() = path;
+        self.loaded.store(true, Ordering::Release);
         Ok(())
     }
 
-    pub fn transcribe(&self, audio_data: &[f32]) -> Result<String> {
+    // Variante empruntée : copie le slice, préférer `transcribe_owned` si possible
+    pub fn transcribe(&self, audio_data: &[f32]) -> Result<Transcription> {
+        self.transcribe_owned(audio_data.to_vec())
+    }
+
+    // Prend possession du buffer : sans découpage, il est passé tel quel au
+    // moteur, sans copie (pic mémoire divisé par deux sur les longs enregistrements)
+    pub fn transcribe_owned(&self, audio_data: Vec<f32>) -> Result<Transcription> {
+        self.transcribe_owned_with_progress(audio_data, |_| {})
+    }
+
+    // Fichier audio (WAV uniquement), avec les mêmes réglages que le micro
+    pub fn transcribe_file(&self, path: &Path) -> Result<Transcription> {
+        let samples = audio::read_wav(path).map_err(|e| anyhow!("Cannot read {:?}: {}", path, e))?;
+        info!("Transcribing file {:?} ({} samples)", path, samples.len());
+        self.transcribe_owned(samples)
+    }
+
+    // transcribe-rs n'expose pas le callback de progression de Whisper :
+    // la progression (0.0..=1.0) est rapportée après chaque morceau transcrit.
+    pub fn transcribe_with_progress(&self, audio_data: &[f32], on_progress: impl FnMut(f64)) -> Result<Transcription> {
+        self.transcribe_owned_with_progress(audio_data.to_vec(), on_progress)
+    }
+
+    fn transcribe_owned_with_progress(&self, audio_data: Vec<f32>, mut on_progress: impl FnMut(f64)) -> Result<Transcription> {
+        // Copie des réglages : un changement (de langue...) pendant la
+        // transcription ne vaut que pour la suivante, tous morceaux compris
+        let options = self.options.lock().unwrap().clone();
+
+        // Les morceaux sont contigus : seules leurs longueurs sont gardées,
+        // chacun n'est copié qu'au moment de sa transcription.
+        let chunk_lengths: Vec<usize> = if options.split_on_silence {
+            audio::split_on_silence(&audio_data, SPLIT_MIN_SILENCE_MS).iter().map(|chunk| chunk.len()).collect()
+        } else {
+            vec![audio_data.len()]
+        };
+        if chunk_lengths.len() > 1 {
+            info!("Transcribing {} chunks split on silence", chunk_lengths.len());
+        }
+
         let mut guard = self.engine.lock().unwrap();
         let engine = guard.as_mut().ok_or(anyhow!("Engine not loaded"))?;
-        
-        let params = WhisperInferenceParams {
-            language: Some("fr".to_string()),
-            print_progress: false,
-            print_realtime: false,
-            print_timestamps: false,
-            // Optimisation "Prompt Priming" : 
-            // Guide le style pour avoir de la ponctuation et une bonne syntaxe.
-            initial_prompt: Some("Voici une transcription claire, concise et bien ponctuée en français.".to_string()),
-            ..Default::default()
-        };
-        
-        let transcript = TranscriptionEngine::transcribe_samples(engine, audio_data.to_vec(), Some(params))
-             .map_err(|e| anyhow!("Transcription failed: {}", e))?;
-            
-        Ok(transcript.text)
+
+        // Les morceaux sont transcrits dans l'ordre, séparés par un espace
+        let chunk_count = chunk_lengths.len();
+        let mut texts = Vec::with_capacity(chunk_count);
+        let mut segments = Vec::new();
+        let mut owned = Some(audio_data);
+        let mut offset = 0;
+        // Un segment ne commence une phrase que si le précédent la termine
+        let mut sentence_start = true;
+        for (i, len) in chunk_lengths.into_iter().enumerate() {
+            let chunk = if chunk_count == 1 {
+                owned.take().unwrap_or_default()
+            } else {
+                owned.as_ref().map(|audio| audio[offset..offset + len].to_vec()).unwrap_or_default()
+            };
+            // Horodatages du morceau décalés de sa position dans l'audio complet
+            let chunk_start = offset as f32 / audio::WHISPER_SAMPLE_RATE as f32;
+            offset += len;
+            let params = inference_params(options.language.as_deref());
+            let transcript = TranscriptionEngine::transcribe_samples(engine, chunk, Some(params))
+                 .map_err(|e| anyhow!("Transcription failed: {}", e))?;
+            for segment in transcript.segments.iter().flatten() {
+                let text = segment.text.trim();
+                if !text.is_empty() {
+                    segments.push(Segment {
+                        start: chunk_start + segment.start,
+                        end: chunk_start + segment.end,
+                        text: finish_text(text, &options, sentence_start),
+                    });
+                    sentence_start = text.ends_with(['.', '!', '?', '…']);
+                }
+            }
+            let text = transcript.text.trim();
+            if !text.is_empty() {
+                texts.push(text.to_string());
+            }
+            on_progress((i + 1) as f64 / chunk_count as f64);
+        }
+
+        // transcribe-rs ne remonte pas la langue détectée par Whisper :
+        // en mode auto, on le signale simplement par `None`.
+        if options.language.is_none() {
+            info!("Language auto-detected (not reported by the engine)");
+        }
+
+        Ok(Transcription {
+            text: finish_text(&texts.join(" "), &options, true),
+            language: options.language,
+            segments,
+        })
+    }
+}
+
+// Traitements appliqués au texte brut de Whisper (transcription complète ou
+// segment). `sentence_start` : le texte commence une phrase (majuscule).
+fn finish_text(text: &str, options: &TranscriptionOptions, sentence_start: bool) -> String {
+    let mut text = if options.post_process {
+        clean_up(text, options.language.as_deref(), sentence_start)
+    } else {
+        text.to_string()
+    };
+    // Après le nettoyage pour que le dictionnaire ait le dernier mot
+    for replacement in &options.replacements {
+        text = replace_whole_word(&text, replacement);
+    }
+    if options.profanity_filter {
+        text = mask_profanity(&text, &options.profanity_words);
+    }
+    text
+}
+
+// Sérialisation de la transcription dans le format choisi. `text` est le texte
+// affiché (éventuellement retouché), les segments servent aux formats horodatés.
+pub fn export_transcript(format: ExportFormat, text: &str, segments: &[Segment]) -> Result<String> {
+    if format.needs_segments() && segments.is_empty() {
+        return Err(anyhow!("No timestamps available for {}", format.label()));
+    }
+    match format {
+        ExportFormat::Text => Ok(format!("{}\n", text.trim())),
+        ExportFormat::Srt => Ok(to_srt(segments)),
+        ExportFormat::Vtt => Ok(to_vtt(segments)),
+        ExportFormat::Json => {
+            let value = serde_json::json!({ "text": text.trim(), "segments": segments });
+            Ok(serde_json::to_string_pretty(&value)? + "\n")
+        }
+    }
+}
+
+fn to_srt(segments: &[Segment]) -> String {
+    let mut out = String::new();
+    for (i, segment) in segments.iter().enumerate() {
+        out.push_str(&format!("{}\n{}\n{}\n\n", i + 1, segment.timing(), segment.text));
+    }
+    out
+}
+
+fn to_vtt(segments: &[Segment]) -> String {
+    let mut out = String::from("WEBVTT\n\n");
+    for segment in segments {
+        out.push_str(&format!(
+            "{} --> {}\n{}\n\n",
+            format_timestamp(segment.start, '.'),
+            format_timestamp(segment.end, '.'),
+            segment.text
+        ));
+    }
+    out
+}
+
+// "HH:MM:SS,mmm" (SRT) ou "HH:MM:SS.mmm" (WebVTT)
+fn format_timestamp(seconds: f32, separator: char) -> String {
+    let total_ms = (seconds.max(0.0) * 1000.0).round() as u64;
+    format!(
+        "{:02}:{:02}:{:02}{}{:03}",
+        total_ms / 3_600_000,
+        total_ms / 60_000 % 60,
+        total_ms / 1000 % 60,
+        separator,
+        total_ms % 1000
+    )
+}
+
+// Nettoyage du texte de Whisper : espaces multiples, espace avant la ponctuation
+// et majuscule en début de phrase. En français, la ponctuation double (; : ! ?)
+// et les guillemets gardent une espace insécable au lieu d'être collés.
+pub fn clean_up_text(text: &str, language: Option<&str>) -> String {
+    clean_up(text, language, true)
+}
+
+// `capitalize` : majuscule sur la première lettre, faux pour un segment
+// qui continue la phrase du précédent
+fn clean_up(text: &str, language: Option<&str>, mut capitalize: bool) -> String {
+    let french = language == Some("fr");
+    let collapsed = text.split_whitespace().collect::<Vec<_>>().join(" ");
+
+    let mut out = String::with_capacity(collapsed.len());
+    let mut skip_space = false;
+    let mut chars = collapsed.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if skip_space && c == ' ' {
+            skip_space = false;
+            continue;
+        }
+        skip_space = false;
+
+        match c {
+            ',' | '.' | ')' | '…' => trim_trailing_spaces(&mut out),
+            // Espace insécable seulement entre la fin d'un mot et celle de la
+            // ponctuation : "10:30" et "https://" restent intacts
+            ';' | ':' | '!' | '?' => {
+                trim_trailing_spaces(&mut out);
+                let after_word = out.chars().next_back().is_some_and(|prev| prev.is_alphabetic() || prev == '»');
+                let ends_word = chars.peek().is_none_or(|next| matches!(next, ' ' | ';' | ':' | '!' | '?'));
+                if french && after_word && ends_word {
+                    out.push(NBSP);
+                }
+            }
+            '»' => {
+                trim_trailing_spaces(&mut out);
+                if french && !out.is_empty() {
+                    out.push(NBSP);
+                }
+            }
+            _ => {}
+        }
+
+        if capitalize && c.is_alphabetic() {
+            out.extend(c.to_uppercase());
+        } else {
+            out.push(c);
+        }
+        if c.is_alphanumeric() {
+            capitalize = false;
+        }
+
+        if french && c == '«' {
+            out.push(NBSP);
+            skip_space = true;
+        }
+        // Fin de phrase seulement si suivie d'un espace (évite "3.5")
+        if matches!(c, '.' | '!' | '?' | '…') && chars.peek().is_none_or(|next| *next == ' ') {
+            capitalize = true;
+        }
+    }
+    out
+}
+
+// Remplace par des astérisques les mots de `words` (en minuscules).
+// Découpage sur les caractères non alphanumériques : "con" ne touche pas "contre".
+// Best effort : les variantes orthographiques ou déguisées ne sont pas détectées.
+pub fn mask_profanity(text: &str, words: &[String]) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut word = String::new();
+
+    let flush = |word: &mut String, out: &mut String| {
+        if words.contains(&word.to_lowercase()) {
+            out.extend(std::iter::repeat_n('*', word.chars().count()));
+        } else {
+            out.push_str(word);
+        }
+        word.clear();
+    };
+
+    for c in text.chars() {
+        if c.is_alphanumeric() {
+            word.push(c);
+        } else {
+            flush(&mut word, &mut out);
+            out.push(c);
+        }
+    }
+    flush(&mut word, &mut out);
+    out
+}
+
+// Remplacement mot entier : la correspondance doit être bornée par des
+// caractères non alphanumériques (ou le début/fin du texte).
+pub fn replace_whole_word(text: &str, replacement: &Replacement) -> String {
+    let pattern: Vec<char> = replacement.from.chars().collect();
+    if pattern.is_empty() {
+        return text.to_string();
+    }
+
+    let same = |a: char, b: char| {
+        if replacement.case_insensitive {
+            a.to_lowercase().eq(b.to_lowercase())
+        } else {
+            a == b
+        }
+    };
+
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::with_capacity(text.len());
+    let mut i = 0;
+    while i < chars.len() {
+        let end = i + pattern.len();
+        let matches = (i == 0 || !chars[i - 1].is_alphanumeric())
+            && end <= chars.len()
+            && chars[i..end].iter().zip(&pattern).all(|(&a, &b)| same(a, b))
+            && (end == chars.len() || !chars[end].is_alphanumeric());
+
+        if matches {
+            out.push_str(&replacement.to);
+            i = end;
+        } else {
+            out.push(chars[i]);
+            i += 1;
+        }
+    }
+    out
+}
+
+fn trim_trailing_spaces(out: &mut String) {
+    while out.ends_with(' ') || out.ends_with(NBSP) {
+        out.pop();
+    }
+}
+
+fn inference_params(language: Option<&str>) -> WhisperInferenceParams {
+    // Optimisation "Prompt Priming" : 
+    // Guide le style pour avoir de la ponctuation et une bonne syntaxe.
+    // Le prompt est en français, on ne l'utilise donc que pour le français.
+    let initial_prompt = match language {
+        Some("fr") => Some("Voici une transcription claire, concise et bien ponctuée en français.".to_string()),
+        _ => None,
+    };
+
+    WhisperInferenceParams {
+        language: language.map(str::to_string),
+        print_progress: false,
+        print_realtime: false,
+        print_timestamps: false,
+        initial_prompt,
+        ..Default::default()
+    }
+
+}
+
+// Variante tirée du nom de fichier : "ggml-base-q5_1.bin" -> "base-q5_1"
+fn model_variant(path: &Path) -> String {
+    let stem = path.file_stem().map(|s| s.to_string_lossy()).unwrap_or_default();
+    stem.strip_prefix("ggml-").unwrap_or(&stem).to_string()
+}
+
+fn model_file_name(variant: &str) -> String {
+    format!("ggml-{}.bin", variant)
+}
+
+fn model_memory_mb(path: &Path) -> Option<u64> {
+    let variant = model_variant(path);
+    MODEL_MEMORY_MB
+        .iter()
+        .find(|(size, _)| variant.starts_with(size))
+        .map(|(_, mb)| *mb)
+}
+
+fn memory_warning(path: &Path) -> Option<String> {
+    let variant = model_variant(path);
+    let required = model_memory_mb(path)?;
+    let available = available_memory_mb()?;
+    (available < required).then(|| {
+        format!(
+            "The {} model needs about {} MB of memory but only {} MB is available; loading may fail. Try a smaller model (base or tiny).",
+            variant, required, available
+        )
+    })
+}
+
+// Les anciennes versions cherchaient le modèle dans le dossier courant :
+// un `ggml-<variante>.bin` trouvé là est déplacé dans le dossier des modèles
+// plutôt que retéléchargé (utilisé sur place si le déplacement échoue).
+fn adopt_legacy_model(path: &Path) -> PathBuf {
+    let Some(name) = path.file_name() else {
+        return path.to_path_buf();
+    };
+    let legacy = PathBuf::from(name);
+    if path.exists() || !legacy.is_file() {
+        return path.to_path_buf();
+    }
+
+    let moved = match path.parent() {
+        Some(dir) => std::fs::create_dir_all(dir).and_then(|_| std::fs::rename(&legacy, path)),
+        None => std::fs::rename(&legacy, path),
+    };
+    match moved {
+        Ok(()) => {
+            info!("Moved model {:?} to {:?}", legacy, path);
+            path.to_path_buf()
+        }
+        Err(e) => {
+            warn!("Cannot move model {:?} to {:?} ({}), using it in place", legacy, path, e);
+            legacy
+        }
+    }
+}
+
+fn ensure_model_file(path: &Path) -> Result<()> {
+    if path.exists() {
+        return Ok(());
+    }
+
+    if let Some(dir) = path.parent() {
+        ensure_writable_dir(dir)?;
+    }
+
+    info!("Downloading optimized model to {:?}", path);
+
+    // Téléchargement dans un fichier temporaire, renommé une fois complet.
+    // Il est conservé entre les tentatives pour pouvoir reprendre le téléchargement.
+    let part_path = path.with_extension("bin.part");
+    let url = format!("{}/{}", MODEL_BASE_URL, path.file_name().map(|n| n.to_string_lossy()).unwrap_or_default());
+    let runtime = tokio::runtime::Runtime::new()?;
+
+    let mut attempt = 1;
+    loop {
+        match runtime.block_on(download_to_file(&url, &part_path)) {
+            Ok(()) => break,
+            Err(e) if attempt < DOWNLOAD_ATTEMPTS => {
+                // Backoff exponentiel : 1s, 2s, ...
+                let delay = Duration::from_secs(1 << (attempt - 1));
+                warn!("Model download failed (attempt {}/{}): {}. Retrying in {:?}", attempt, DOWNLOAD_ATTEMPTS, e, delay);
+                std::thread::sleep(delay);
+                attempt += 1;
+            }
+            Err(e) => {
+                return Err(anyhow!("Model download failed after {} attempts: {}", DOWNLOAD_ATTEMPTS, e));
+            }
+        }
+    }
+
+    std::fs::rename(&part_path, path)?;
+    info!("Model downloaded.");
+    Ok(())
+}
+
+fn looks_like_allocation_failure(message: &str) -> bool {
+    let message = message.to_lowercase();
+    ["alloc", "memory"].iter().any(|needle| message.contains(needle))
+}
+
+// Mémoire disponible en Mo, lue dans /proc/meminfo (Linux uniquement)
+fn available_memory_mb() -> Option<u64> {
+    let meminfo = std::fs::read_to_string("/proc/meminfo").ok()?;
+    let line = meminfo.lines().find(|line| line.starts_with("MemAvailable:"))?;
+    let kb: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kb / 1024)
+}
+
+// Crée le dossier si besoin et vérifie qu'on peut y écrire avant de télécharger
+fn ensure_writable_dir(dir: &Path) -> Result<()> {
+    std::fs::create_dir_all(dir).map_err(|e| anyhow!("Cannot create model directory {:?}: {}", dir, e))?;
+    let probe = dir.join(".nspeech-write-test");
+    std::fs::write(&probe, b"")
+        .map_err(|e| anyhow!("Model directory {:?} is not writable ({}); set {} to another directory", dir, e, MODEL_DIR_ENV))?;
+    let _ = std::fs::remove_file(probe);
+    Ok(())
+}
+
+// Fichier décrit par Hugging Face avant la redirection vers le CDN :
+// `X-Linked-Size` (taille) et `X-Linked-Etag` (SHA-256 des fichiers LFS)
+#[derive(Debug, Default)]
+struct LinkedFile {
+    size: Option<u64>,
+    sha256: Option<String>,
+}
+
+// Requête HEAD sans suivre la redirection. Sans réponse exploitable, le
+// téléchargement continue avec la seule vérification de la taille.
+async fn linked_file(url: &str) -> LinkedFile {
+    let client = match reqwest::Client::builder().redirect(reqwest::redirect::Policy::none()).build() {
+        Ok(client) => client,
+        Err(e) => {
+            warn!("Cannot check the model checksum: {}", e);
+            return LinkedFile::default();
+        }
+    };
+    let resp = match client.head(url).send().await {
+        Ok(resp) => resp,
+        Err(e) => {
+            warn!("Cannot fetch the model checksum: {}", e);
+            return LinkedFile::default();
+        }
+    };
+    let header = |name: &str| {
+        resp.headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.trim_matches('"').to_lowercase())
+    };
+    // Hors LFS, l'etag est un SHA-1 git : inutilisable ici
+    let sha256 = header("x-linked-etag").filter(|etag| etag.len() == 64 && etag.chars().all(|c| c.is_ascii_hexdigit()));
+    if sha256.is_none() {
+        warn!("No SHA-256 published for {}, the download is only checked by size", url);
+    }
+    LinkedFile {
+        size: header("x-linked-size").and_then(|size| size.parse().ok()),
+        sha256,
+    }
+}
+
+// Téléchargement en streaming pour ne pas garder tout le modèle en mémoire.
+// Si `dest` contient déjà un début de fichier, on tente de reprendre avec une
+// requête HTTP Range ; sinon (ou si le serveur l'ignore) on repart de zéro.
+// Le fichier final est vérifié par sa taille puis par son SHA-256.
+async fn download_to_file(url: &str, dest: &Path) -> Result<()> {
+    let linked = linked_file(url).await;
+    let mut existing = tokio::fs::metadata(dest).await.map(|m| m.len()).unwrap_or(0);
+
+    match linked.size {
+        // Fichier partiel déjà complet (ex. renommage raté la dernière fois)
+        Some(size) if existing == size => {
+            info!("Partial model file is already complete, verifying it");
+            return verify_download(dest, &linked).await;
+        }
+        Some(size) if existing > size => {
+            let _ = tokio::fs::remove_file(dest).await;
+            existing = 0;
+        }
+        _ => {}
+    }
+
+    let mut request = reqwest::Client::new().get(url);
+    if existing > 0 {
+        request = request.header(reqwest::header::RANGE, format!("bytes={}-", existing));
+    }
+    let resp = request.send().await?;
+
+    if resp.status() == reqwest::StatusCode::RANGE_NOT_SATISFIABLE {
+        // Le 416 annonce la taille totale (`bytes */total`) : un fichier
+        // partiel de cette taille est complet, il n'y a rien à reprendre
+        if content_range_total(&resp) == Some(existing) {
+            return verify_download(dest, &linked).await;
+        }
+        // Fichier partiel incohérent avec le serveur : on le jette
+        let _ = tokio::fs::remove_file(dest).await;
+        return Err(anyhow!("Partial download rejected by server, restarting from scratch"));
+    }
+    let resp = resp.error_for_status()?;
+
+    // Un 206 signifie que le serveur supporte les Range (Accept-Ranges)
+    let resuming = existing > 0 && resp.status() == reqwest::StatusCode::PARTIAL_CONTENT;
+    let expected_len = if resuming {
+        content_range_total(&resp)
+    } else {
+        resp.content_length()
+    };
+
+    let mut file = if resuming {
+        info!("Resuming model download from {} bytes", existing);
+        tokio::fs::OpenOptions::new().append(true).open(dest).await?
+    } else {
+        if existing > 0 {
+            info!("Server does not support range requests, restarting model download");
+        }
+        tokio::fs::File::create(dest).await?
+    };
+
+    let mut stream = resp.bytes_stream();
+    while let Some(chunk) = stream.next().await {
+        file.write_all(&chunk?).await?;
+    }
+    file.flush().await?;
+
+    // Vérification de la taille finale pour détecter une reprise tronquée
+    if let Some(expected) = expected_len.or(linked.size) {
+        let actual = tokio::fs::metadata(dest).await?.len();
+        if actual != expected {
+            let _ = tokio::fs::remove_file(dest).await;
+            return Err(anyhow!("Downloaded model is {} bytes, expected {}", actual, expected));
+        }
+    }
+    verify_download(dest, &linked).await
+}
+
+// Une reprise peut recoller des octets corrompus sans changer la taille :
+// seul le SHA-256 le détecte. Le fichier fautif est supprimé.
+async fn verify_download(dest: &Path, linked: &LinkedFile) -> Result<()> {
+    let Some(expected) = &linked.sha256 else {
+        return Ok(());
+    };
+    let path = dest.to_path_buf();
+    let actual = tokio::task::spawn_blocking(move || sha256_file(&path)).await??;
+    if &actual != expected {
+        let _ = tokio::fs::remove_file(dest).await;
+        return Err(anyhow!("Downloaded model is corrupted (SHA-256 {}, expected {})", actual, expected));
+    }
+    info!("Model checksum verified.");
+    Ok(())
+}
+
+fn sha256_file(path: &Path) -> Result<String> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+// Taille totale annoncée par l'en-tête `Content-Range: bytes start-end/total`
+fn content_range_total(resp: &reqwest::Response) -> Option<u64> {
+    resp.headers()
+        .get(reqwest::header::CONTENT_RANGE)?
+        .to_str()
+        .ok()?
+        .rsplit('/')
+        .next()?
+        .parse()
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sha256_file_matches_known_digest() {
+        let path = std::env::temp_dir().join(format!("nspeech-sha256-{}", std::process::id()));
+        std::fs::write(&path, b"abc").unwrap();
+        let digest = sha256_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(digest, "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+    }
+
+    #[test]
+    fn clean_up_collapses_spaces_and_capitalizes() {
+        assert_eq!(clean_up_text("hello ,  world !  how are you ?", Some("en")), "Hello, world! How are you?");
+    }
+
+    #[test]
+    fn clean_up_adds_nbsp_before_french_double_punctuation() {
+        assert_eq!(clean_up_text("bonjour , ça va ?", Some("fr")), "Bonjour, ça va\u{a0}?");
+        assert_eq!(clean_up_text("attention : danger", Some("fr")), "Attention\u{a0}: danger");
+        assert_eq!(clean_up_text("quoi ?!", Some("fr")), "Quoi\u{a0}?!");
+    }
+
+    #[test]
+    fn clean_up_keeps_french_quotes_spaced() {
+        assert_eq!(clean_up_text("« bonjour »", Some("fr")), "«\u{a0}Bonjour\u{a0}»");
+    }
+
+    #[test]
+    fn clean_up_leaves_times_and_urls_alone() {
+        assert_eq!(clean_up_text("rendez-vous à 10:30.", Some("fr")), "Rendez-vous à 10:30.");
+        assert_eq!(clean_up_text("voir https://example.com", Some("fr")), "Voir https://example.com");
+    }
+
+    #[test]
+    fn clean_up_keeps_decimal_points() {
+        assert_eq!(clean_up_text("version 3.5 is out", Some("en")), "Version 3.5 is out");
+    }
+
+    #[test]
+    fn finish_text_capitalizes_only_sentence_starts() {
+        let options = TranscriptionOptions::default();
+        assert_eq!(finish_text("et puis nous sommes partis.", &options, false), "et puis nous sommes partis.");
+        assert_eq!(finish_text("et puis nous sommes partis.", &options, true), "Et puis nous sommes partis.");
+    }
+
+    fn segments() -> Vec<Segment> {
+        vec![
+            Segment { start: 0.0, end: 1.5, text: "Hello.".to_string() },
+            Segment { start: 1.5, end: 3.25, text: "World.".to_string() },
+        ]
+    }
+
+    #[test]
+    fn format_timestamp_uses_separator_and_hours() {
+        assert_eq!(format_timestamp(1.2, ','), "00:00:01,200");
+        assert_eq!(format_timestamp(1.2, '.'), "00:00:01.200");
+        assert_eq!(format_timestamp(3723.456, ','), "01:02:03,456");
+        assert_eq!(format_timestamp(-1.0, ','), "00:00:00,000");
+    }
+
+    #[test]
+    fn srt_numbers_cues_with_comma_milliseconds() {
+        assert_eq!(
+            export_transcript(ExportFormat::Srt, "Hello. World.", &segments()).unwrap(),
+            "1\n00:00:00,000 --> 00:00:01,500\nHello.\n\n2\n00:00:01,500 --> 00:00:03,250\nWorld.\n\n"
+        );
+    }
+
+    #[test]
+    fn vtt_has_header_and_dot_milliseconds() {
+        assert_eq!(
+            export_transcript(ExportFormat::Vtt, "Hello. World.", &segments()).unwrap(),
+            "WEBVTT\n\n00:00:00.000 --> 00:00:01.500\nHello.\n\n00:00:01.500 --> 00:00:03.250\nWorld.\n\n"
+        );
+    }
+
+    #[test]
+    fn json_export_has_text_and_segments() {
+        let json = export_transcript(ExportFormat::Json, " Hello. World. ", &segments()).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["text"], "Hello. World.");
+        assert_eq!(value["segments"].as_array().unwrap().len(), 2);
+        assert_eq!(value["segments"][1]["start"], 1.5);
+        assert_eq!(value["segments"][1]["end"], 3.25);
+        assert_eq!(value["segments"][1]["text"], "World.");
+    }
+
+    #[test]
+    fn timed_exports_need_segments() {
+        for format in [ExportFormat::Srt, ExportFormat::Vtt, ExportFormat::Json] {
+            assert!(export_transcript(format, "Hello.", &[]).is_err());
+        }
+        assert_eq!(export_transcript(ExportFormat::Text, " Hello. ", &[]).unwrap(), "Hello.\n");
     }
 }
@@ -0,0 +1,96 @@
+// Tests du pipeline audio -> transcription, sur les modules de l'application.
+// Les tests sans modèle tournent toujours. Celui qui charge Whisper (tiny) ne
+// tourne qu'avec NSPEECH_MODEL_TESTS=1 : il télécharge le modèle si besoin
+// (voir NSPEECH_MODEL_DIR) et transcrit tests/fixtures/jfk.wav.
+// Le VAD, le downmix et les filtres ont leurs tests unitaires dans audio.rs.
+
+use nspeech::audio::{self, WHISPER_SAMPLE_RATE};
+use nspeech::transcription::TranscriptionManager;
+use std::path::PathBuf;
+
+const MODEL_TESTS_ENV: &str = "NSPEECH_MODEL_TESTS";
+const TEST_MODEL: &str = "tiny-q5_1";
+// Mots attendus dans "And so my fellow Americans, ask not what your country can do for you..."
+const EXPECTED_WORDS: &[&str] = &["fellow", "americans", "ask", "country"];
+
+// Sinusoïde de 440 Hz, assez forte pour passer tous les seuils de silence
+fn tone(samples: usize, sample_rate: u32) -> Vec<f32> {
+    (0..samples)
+        .map(|i| 0.5 * (2.0 * std::f32::consts::PI * 440.0 * i as f32 / sample_rate as f32).sin())
+        .collect()
+}
+
+fn silence(samples: usize) -> Vec<f32> {
+    vec![0.0; samples]
+}
+
+fn temp_wav(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("nspeech-{}-{}.wav", name, std::process::id()))
+}
+
+#[test]
+fn resample_keeps_duration() {
+    let input = tone(48000, 48000);
+    let output = audio::resample_simple(&input, 48000, WHISPER_SAMPLE_RATE);
+    assert!((output.len() as i64 - WHISPER_SAMPLE_RATE as i64).abs() <= 1, "got {} samples", output.len());
+}
+
+#[test]
+fn wav_round_trip_is_mono_16k() {
+    let path = temp_wav("round-trip");
+    audio::write_wav(&path, &tone(44100, 44100), 44100).unwrap();
+    let samples = audio::read_wav(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert!((samples.len() as i64 - WHISPER_SAMPLE_RATE as i64).abs() <= 1, "got {} samples", samples.len());
+    let peak = samples.iter().fold(0.0f32, |max, &x| max.max(x.abs()));
+    assert!((peak - 0.5).abs() < 0.05, "peak {}", peak);
+}
+
+#[test]
+fn trim_silence_keeps_speech_and_padding() {
+    let mut samples = silence(16000);
+    samples.extend(tone(8000, WHISPER_SAMPLE_RATE));
+    samples.extend(silence(16000));
+
+    audio::trim_silence(&mut samples, 0.01);
+    assert!(samples.len() >= 8000, "speech was cut: {} samples", samples.len());
+    assert!(samples.len() < 40000, "silence was not trimmed: {} samples", samples.len());
+}
+
+#[test]
+fn trim_silence_empties_pure_silence() {
+    let mut samples = silence(16000);
+    audio::trim_silence(&mut samples, 0.01);
+    assert!(samples.is_empty());
+}
+
+#[test]
+fn split_on_silence_cuts_long_pauses() {
+    let mut samples = tone(16000, WHISPER_SAMPLE_RATE);
+    samples.extend(silence(16000));
+    samples.extend(tone(16000, WHISPER_SAMPLE_RATE));
+
+    let chunks = audio::split_on_silence(&samples, 500);
+    assert_eq!(chunks.len(), 2);
+    assert_eq!(chunks.iter().map(|chunk| chunk.len()).sum::<usize>(), samples.len());
+}
+
+#[test]
+fn transcribes_fixture_with_tiny_model() {
+    if std::env::var_os(MODEL_TESTS_ENV).is_none() {
+        eprintln!("Skipped: set {}=1 to run the Whisper model test", MODEL_TESTS_ENV);
+        return;
+    }
+    let fixture = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/jfk.wav");
+    assert!(fixture.exists(), "Missing {:?}, see tests/fixtures/README.md", fixture);
+
+    let transcriber = TranscriptionManager::new(&TranscriptionManager::default_model_dir(), TEST_MODEL);
+    transcriber.set_language(Some("en".to_string()));
+    transcriber.load_model().unwrap();
+    let text = transcriber.transcribe_file(&fixture).unwrap().text.to_lowercase();
+
+    // Le modèle tiny peut écorcher un mot : la plupart doivent être là
+    let found = EXPECTED_WORDS.iter().filter(|word| text.contains(*word)).count();
+    assert!(found >= EXPECTED_WORDS.len() - 1, "unexpected transcript: {:?}", text);
+}